@@ -1,6 +1,10 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use emp_tool::{aes::Aes, block::Block};
+use emp_tool::{
+    aes::Aes,
+    block::Block,
+    cpu_features::{Backend, CpuFeatures},
+};
 
 fn criterion_benchmark(c: &mut Criterion) {
     let x = rand::random::<Block>();
@@ -38,8 +42,42 @@ fn criterion_benchmark(c: &mut Criterion) {
         let mut blks = rand::random::<[Block; 8]>();
 
         bench.iter(|| {
-            let z = aes.encrypt_block_slice(black_box(&mut blks));
-            black_box(z);
+            aes.encrypt_block_slice(black_box(&mut blks));
+            black_box(&blks);
+        });
+    });
+
+    c.bench_function("aes::encrypt_block_slice::<1024>", move |bench| {
+        let key = rand::random::<Block>();
+        let aes = Aes::new(key);
+        let mut blks = vec![Block::ZERO; 1024];
+
+        bench.iter(|| {
+            aes.encrypt_block_slice(black_box(&mut blks));
+            black_box(&blks);
+        });
+    });
+
+    c.bench_function("aes::encrypt_block::<1024>", move |bench| {
+        let key = rand::random::<Block>();
+        let aes = Aes::new(key);
+        let mut blks = vec![Block::ZERO; 1024];
+
+        bench.iter(|| {
+            for blk in blks.iter_mut() {
+                *blk = aes.encrypt_block(black_box(*blk));
+            }
+            black_box(&blks);
+        });
+    });
+
+    c.bench_function("aes::para_encrypt::<2,4>", move |bench| {
+        let keys = rand::random::<[Block; 2]>().map(Aes::new);
+        let mut blks = rand::random::<[Block; 8]>();
+
+        bench.iter(|| {
+            Aes::para_encrypt::<2, 4>(black_box(keys), black_box(&mut blks));
+            black_box(&blks);
         });
     });
 
@@ -50,10 +88,38 @@ fn criterion_benchmark(c: &mut Criterion) {
         let mut blks = rand::random::<[Block; 8]>();
 
         bench.iter(|| {
-            let z = Aes::para_encrypt::<1, 8>(black_box(aes), black_box(&mut blks));
-            black_box(z);
+            Aes::para_encrypt::<1, 8>(black_box(aes), black_box(&mut blks));
+            black_box(&blks);
         });
     });
+
+    c.bench_function("aes::para_encrypt_slice::<3,1024>", move |bench| {
+        let keys = Aes::para_new(rand::random::<[Block; 3]>());
+        let mut blks = vec![Block::ZERO; 3 * 1024];
+
+        bench.iter(|| {
+            Aes::para_encrypt_slice(black_box(&keys), black_box(&mut blks));
+            black_box(&blks);
+        });
+    });
+
+    // Run the backends side by side on the same inputs.
+    let features = CpuFeatures::get();
+    let mut group = c.benchmark_group("aes::encrypt_block_slice_with::<1024>");
+    let blks: Vec<Block> = (0..1024).map(|_| rand::random::<Block>()).collect();
+    for backend in [Backend::Soft, Backend::Simd, Backend::Wide] {
+        if !features.supports_aes(backend) {
+            continue;
+        }
+        let mut x = blks.clone();
+        group.bench_function(format!("{:?}", backend), |bench| {
+            bench.iter(|| {
+                aes.encrypt_block_slice_with(black_box(&mut x), backend);
+                black_box(&x);
+            });
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -1,6 +1,9 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use emp_tool::block::Block;
+use emp_tool::{
+    block::Block,
+    cpu_features::{Backend, CpuFeatures},
+};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha12Rng;
 
@@ -19,6 +22,9 @@ fn criterion_benchmark(c: &mut Criterion) {
     }
     let t = x.clone();
     let f = y.clone();
+    let u = x.clone();
+    let v = y.clone();
+    let (p, q) = (x.clone(), y.clone());
 
     let exp = rand::random::<u128>();
 
@@ -94,6 +100,16 @@ fn criterion_benchmark(c: &mut Criterion) {
         });
     });
 
+    c.bench_function("Block::gfmul::<1000>", move |bench| {
+        bench.iter(|| {
+            black_box(
+                u.iter()
+                    .zip(v.iter())
+                    .fold(Block::ZERO, |acc, (x, y)| acc ^ x.gfmul(y)),
+            );
+        });
+    });
+
     c.bench_function("Block::pow", move |bench| {
         bench.iter(|| {
             black_box(a.pow(exp));
@@ -111,6 +127,32 @@ fn criterion_benchmark(c: &mut Criterion) {
             black_box(Block::sigma(a));
         });
     });
+
+    // Run the backends side by side on the same inputs.
+    let features = CpuFeatures::get();
+    let backends = [Backend::Soft, Backend::Simd, Backend::Wide]
+        .into_iter()
+        .filter(|b| features.supports_clmul(*b));
+
+    let mut group = c.benchmark_group("Block::inn_prod_no_red_with");
+    for backend in backends.clone() {
+        group.bench_function(format!("{:?}", backend), |bench| {
+            bench.iter(|| {
+                black_box(Block::inn_prdt_no_red_with(&p, &q, backend));
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("Block::inn_prod_red_with");
+    for backend in backends {
+        group.bench_function(format!("{:?}", backend), |bench| {
+            bench.iter(|| {
+                black_box(Block::inn_prdt_red_with(&p, &q, backend));
+            });
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,40 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use emp_tool::{prg::Prg, Block, Dcf, Dpf};
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("dpf::gen::2^20", move |bench| {
+        let dpf = Dpf::new(20);
+        let mut prg = Prg::new();
+        let beta = rand::random::<Block>();
+        bench.iter(|| {
+            black_box(dpf.gen(black_box(12345), black_box(beta), &mut prg));
+        });
+    });
+
+    c.bench_function("dpf::eval_all::2^20", move |bench| {
+        let depth = 20;
+        let dpf = Dpf::new(depth);
+        let mut prg = Prg::new();
+        let (k0, _) = dpf.gen(12345, rand::random::<Block>(), &mut prg);
+        let mut out = vec![Block::ZERO; 1 << depth];
+        bench.iter(|| {
+            dpf.eval_all(black_box(&k0), black_box(&mut out));
+            black_box(&out);
+        });
+    });
+
+    c.bench_function("dcf::eval_batch::<64>::1K", move |bench| {
+        let dcf = Dcf::new(64);
+        let mut prg = Prg::new();
+        let (k0, _) = dcf.gen(12345, rand::random::<Block>(), &mut prg);
+        let xs: Vec<u64> = (0..1024).map(|_| rand::random::<u64>()).collect();
+        let mut out = vec![Block::ZERO; xs.len()];
+        bench.iter(|| {
+            dcf.eval_batch(black_box(&k0), black_box(&xs), black_box(&mut out));
+            black_box(&out);
+        });
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,5 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use emp_tool::{ggm_tree::GgmTree, Block};
+use emp_tool::{
+    ggm_tree::{GgmTree, QaryGgmTree},
+    Block,
+};
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("ggm::gen::16M", move |bench| {
@@ -10,12 +13,13 @@ fn criterion_benchmark(c: &mut Criterion) {
         let mut k1 = vec![Block::ZERO; depth];
         let seed = rand::random::<Block>();
         bench.iter(|| {
-            black_box(ggm.gen(
+            ggm.gen(
                 black_box(seed),
                 black_box(&mut tree),
                 black_box(&mut k0),
                 black_box(&mut k1),
-            ));
+            );
+            black_box(&tree);
         });
     });
 
@@ -26,7 +30,38 @@ fn criterion_benchmark(c: &mut Criterion) {
         let k = vec![Block::ZERO; depth];
         let alpha = vec![false; depth];
         bench.iter(|| {
-            black_box(ggm.reconstruct(black_box(&alpha), black_box(&k), black_box(&mut tree)))
+            ggm.reconstruct(black_box(&alpha), black_box(&k), black_box(&mut tree));
+            black_box(&tree);
+        });
+    });
+
+    c.bench_function("ggm::gen_many::<1024>::1M", move |bench| {
+        let (t, depth) = (1024, 10);
+        let ggm = GgmTree::new(depth);
+        let mut trees = vec![Block::ZERO; t << depth];
+        let mut k0 = vec![Block::ZERO; t * depth];
+        let mut k1 = vec![Block::ZERO; t * depth];
+        let seeds: Vec<Block> = (0..t).map(|_| rand::random::<Block>()).collect();
+        bench.iter(|| {
+            ggm.gen_many(
+                black_box(&seeds),
+                black_box(&mut trees),
+                black_box(&mut k0),
+                black_box(&mut k1),
+            );
+            black_box(&trees);
+        });
+    });
+
+    c.bench_function("ggm::qary_gen::<16>::16M", move |bench| {
+        let (q, depth) = (16, 6);
+        let ggm = QaryGgmTree::new(q, depth);
+        let mut tree = vec![Block::ZERO; 1 << 24];
+        let mut sums = vec![Block::ZERO; q * depth];
+        let seed = rand::random::<Block>();
+        bench.iter(|| {
+            ggm.gen(black_box(seed), black_box(&mut tree), black_box(&mut sums));
+            black_box(&tree);
         });
     });
 }
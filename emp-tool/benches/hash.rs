@@ -1,8 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 use emp_tool::{
-    hash::{CcrHash, CrHash, TccrHash},
-    Block,
+    hash::{CcrHash, CrHash, MitCcrHash, TccrHash},
+    Block, UniversalHash,
 };
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -54,6 +54,71 @@ fn criterion_benchmark(c: &mut Criterion) {
             black_box(hash.hash_many_blocks::<8>([Block::ZERO; 8], [1; 8]));
         });
     });
+
+    c.bench_function("hash::cr_block_slice::<1<<20>", move |bench| {
+        let hash = CrHash::new();
+        let mut blks = vec![Block::ZERO; 1 << 20];
+        bench.iter(|| {
+            hash.hash_block_slice(black_box(&mut blks));
+        });
+    });
+
+    c.bench_function("hash::cr_par_block_slice::<1<<20>", move |bench| {
+        let hash = CrHash::new();
+        let mut blks = vec![Block::ZERO; 1 << 20];
+        bench.iter(|| {
+            hash.par_hash_block_slice(black_box(&mut blks));
+        });
+    });
+
+    c.bench_function("hash::ccr_block_slice::<1<<20>", move |bench| {
+        let hash = CcrHash::new();
+        let mut blks = vec![Block::ZERO; 1 << 20];
+        bench.iter(|| {
+            hash.hash_block_slice(black_box(&mut blks));
+        });
+    });
+
+    c.bench_function("hash::ccr_par_block_slice::<1<<20>", move |bench| {
+        let hash = CcrHash::new();
+        let mut blks = vec![Block::ZERO; 1 << 20];
+        bench.iter(|| {
+            hash.par_hash_block_slice(black_box(&mut blks));
+        });
+    });
+
+    c.bench_function("hash::tccr_block_slice::<1<<20>", move |bench| {
+        let hash = TccrHash::new();
+        let mut blks = vec![Block::ZERO; 1 << 20];
+        bench.iter(|| {
+            hash.hash_block_slice(black_box(&mut blks), 0);
+        });
+    });
+
+    c.bench_function("hash::tccr_par_block_slice_into::<1<<20>", move |bench| {
+        let hash = TccrHash::new();
+        let blks = vec![Block::ZERO; 1 << 20];
+        let mut out = vec![Block::ZERO; 1 << 20];
+        bench.iter(|| {
+            hash.par_hash_block_slice_into(black_box(&mut out), black_box(&blks), 0);
+        });
+    });
+
+    c.bench_function("hash::mitccr::<8, 2>", move |bench| {
+        let mut hash = MitCcrHash::<8>::new(rand::random::<Block>());
+        let mut blks = rand::random::<[Block; 16]>();
+        bench.iter(|| {
+            hash.hash::<8, 2>(black_box(&mut blks));
+        });
+    });
+
+    c.bench_function("hash::universal::<1<<16>", move |bench| {
+        let key = rand::random::<Block>();
+        let blks = vec![Block::ONES; 1 << 16];
+        bench.iter(|| {
+            black_box(UniversalHash::hash_blocks_once(key, black_box(&blks)));
+        });
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
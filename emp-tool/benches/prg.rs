@@ -17,7 +17,8 @@ fn criterion_benchmark(c: &mut Criterion) {
         let mut prg = Prg::new();
         let mut x = [false; 10];
         bench.iter(|| {
-            black_box(prg.random_bools(black_box(&mut x)));
+            prg.random_bools(black_box(&mut x));
+            black_box(&x);
         });
     });
 
@@ -58,6 +59,14 @@ fn criterion_benchmark(c: &mut Criterion) {
             prg.random_blocks(black_box(&mut x));
         });
     });
+
+    c.bench_function("Prg::blocks_par", move |bench| {
+        let mut prg = Prg::new();
+        let mut x = vec![Block::ZERO; 1 << 20];
+        bench.iter(|| {
+            prg.random_blocks_par(black_box(&mut x));
+        });
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,132 @@
+// Print the test vectors of `src/emp_compat.rs` with the EMP toolkit.
+//
+// The vectors are computed by the library code itself:
+// - `PRG` from emp-tool/utils/prg.h,
+// - `SPCOT_Sender::ggm_tree_gen` from emp-ot/ferret/spcot_sender.h, which expands the tree with `TwoKeyPRP`,
+// - `LpnF2::task` from emp-ot/ferret/lpn_f2.h with d = 10, i.e., `LpnF2::compute` with one thread and a fixed seed,
+// - `NetIO::send_bool` from emp-tool/io/io_channel.h, whose bytes are read from a plain TCP socket.
+//
+// Build against an installed emp-tool and emp-ot:
+//   g++ -O2 -std=c++11 -march=native emp_compat_vectors.cpp -o emp_compat_vectors -lemp-tool -lssl -lcrypto -pthread
+
+#include "emp-tool/emp-tool.h"
+#include "emp-ot/emp-ot.h"
+
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include <cstdio>
+#include <cstring>
+#include <thread>
+#include <vector>
+
+using namespace emp;
+
+// The port used to capture the bytes written by `NetIO`.
+static const int PORT = 12349;
+
+// Print a block as the `u128` of `Block::from`.
+static void print_block(const char *name, int i, block b) {
+	uint64_t v[2];
+	memcpy(v, &b, 16);
+	printf("%s[%d] = 0x%016llx%016llx\n", name, i, (unsigned long long)v[1], (unsigned long long)v[0]);
+}
+
+// Return the bytes written to the socket by `NetIO::send_bool(data, length)`.
+// The connection only carries this call, and it is closed when the `NetIO` is deleted.
+static std::vector<uint8_t> net_io_send_bool(bool *data, size_t length) {
+	int listener = socket(AF_INET, SOCK_STREAM, 0);
+	int reuse = 1;
+	setsockopt(listener, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse));
+	struct sockaddr_in addr;
+	memset(&addr, 0, sizeof(addr));
+	addr.sin_family = AF_INET;
+	addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	addr.sin_port = htons(PORT);
+	if (bind(listener, (struct sockaddr *)&addr, sizeof(addr)) != 0 || listen(listener, 1) != 0) {
+		perror("listen");
+		exit(1);
+	}
+
+	std::thread sender([data, length]() {
+		NetIO *io = new NetIO("127.0.0.1", PORT, true);
+		io->send_bool(data, length);
+		io->flush();
+		delete io;
+	});
+
+	int conn = accept(listener, nullptr, nullptr);
+	std::vector<uint8_t> bytes;
+	uint8_t buf[256];
+	ssize_t n;
+	while ((n = read(conn, buf, sizeof(buf))) > 0)
+		bytes.insert(bytes.end(), buf, buf + n);
+	sender.join();
+	close(conn);
+	close(listener);
+	return bytes;
+}
+
+int main() {
+	// PRG(&seed, 7): 3 blocks, 20 bytes and 1 block.
+	block seed = makeBlock(0x0123456789abcdefULL, 0xfedcba9876543210ULL);
+	PRG prg(&seed, 7);
+	block blks[3];
+	prg.random_block(blks, 3);
+	for (int i = 0; i < 3; ++i)
+		print_block("prg", i, blks[i]);
+	uint8_t bytes[20];
+	prg.random_data(bytes, 20);
+	printf("prg_data =");
+	for (int i = 0; i < 20; ++i)
+		printf(" %02x", bytes[i]);
+	printf("\n");
+	prg.random_block(blks, 1);
+	print_block("prg_last", 0, blks[0]);
+
+	// SPCOT_Sender::ggm_tree_gen with depth 4, i.e., 8 leaves, from the root `seed`.
+	// The sender does not use its IO to generate the tree.
+	int depth = 4;
+	block tree[8];
+	SPCOT_Sender<NetIO> spcot(nullptr, depth);
+	spcot.seed = makeBlock(0x1111111111111111ULL, 0x2222222222222222ULL);
+	block *k0 = spcot.m, *k1 = spcot.m + depth - 1;
+	spcot.ggm_tree_gen(k0, k1, tree, makeBlock(0, 1));
+	for (int i = 0; i < 8; ++i)
+		print_block("ggm", i, tree[i]);
+	for (int i = 0; i < depth - 1; ++i) {
+		print_block("ggm_k0", i, k0[i]);
+		print_block("ggm_k1", i, k1[i]);
+	}
+
+	// LpnF2 with k = 37 and n = 10, where x and y are from PRG(&x_seed).
+	// `task` over all rows is what `compute` runs with one thread after the seed is agreed on.
+	int k = 37, n = 10;
+	block x_seed = makeBlock(3, 4);
+	PRG xprg(&x_seed);
+	std::vector<block> x(k), y(n);
+	xprg.random_block(x.data(), k);
+	xprg.random_block(y.data(), n);
+	LpnF2<NetIO, 10> lpn(ALICE, n, k, nullptr, nullptr, 1);
+	lpn.seed = makeBlock(5, 6);
+	lpn.task(y.data(), x.data(), 0, n);
+	for (int i = 0; i < n; ++i)
+		print_block("lpn", i, y[i]);
+
+	// NetIO::send_bool(buf + offset, len), where `buf` is 8-byte aligned, and bool i is true iff i % 3 == 0 or i % 5 == 0.
+	const size_t cases[][2] = {{0, 19}, {3, 19}, {5, 8}, {7, 24}};
+	for (const auto &c : cases) {
+		size_t offset = c[0], len = c[1];
+		alignas(8) bool buf[64];
+		for (size_t i = 0; i < len; ++i)
+			buf[offset + i] = i % 3 == 0 || i % 5 == 0;
+		std::vector<uint8_t> sent = net_io_send_bool(buf + offset, len);
+		printf("bools(%zu, %zu) =", offset, len);
+		for (uint8_t b : sent)
+			printf(" %02x", b);
+		printf("\n");
+	}
+	return 0;
+}
@@ -44,7 +44,7 @@ fn netio_perf(io: &mut NetIO, party: usize) {
 pub fn main() {
     let opt = CommandLineOpt::from_args();
     let party = opt.party;
-    let is_server = if party == ALICE { true } else { false };
+    let is_server = party == ALICE;
     let mut io = NetIO::new(is_server, "127.0.0.1:12345").unwrap();
     netio_perf(&mut io, party);
 }
@@ -1,4 +1,4 @@
-//! Implement aes128
+//! Implement aes128 encryption and decryption, and aes256 encryption
 #[cfg(target_arch = "aarch64")]
 use core::arch::aarch64::*;
 
@@ -17,7 +17,64 @@ use crate::{
     _mm_shuffle_epi32, _mm_shuffle_ps, _mm_xor_si128,
 };
 
-use crate::Block;
+use crate::{
+    cpu_features::{Backend, CpuFeatures},
+    Block,
+};
+
+#[cfg_attr(target_arch = "aarch64", allow(dead_code))]
+mod soft;
+
+/// A block cipher with 128-bit blocks, e.g., `Aes` and `Aes256`.
+pub trait BlockCipher: Sized {
+    /// The type of the key.
+    type Key;
+
+    /// New an instance with key.
+    fn new(key: Self::Key) -> Self;
+
+    /// Encrypt one block.
+    fn encrypt_block(&self, blk: Block) -> Block;
+
+    /// Encrypt many blocks.
+    fn encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N];
+
+    /// Encrypt block slice.
+    #[inline(always)]
+    fn encrypt_block_slice(&self, blks: &mut [Block]) {
+        let mut chunks = blks.chunks_exact_mut(8);
+        let mut buf = [Block::ZERO; 8];
+        for chunk in &mut chunks {
+            buf.copy_from_slice(chunk);
+            chunk.copy_from_slice(&self.encrypt_many_blocks(buf));
+        }
+
+        for blk in chunks.into_remainder() {
+            *blk = self.encrypt_block(*blk);
+        }
+    }
+
+    /// Encrypt many blocks with many keys.
+    /// Input: `NK` keys `keys`, and `NK * NM` blocks `blks`
+    /// Output: each batch of NM blocks encrypted by a corresponding key.
+    /// Only handle the first `NK * NM` blocks of blks, do not handle the rest.
+    #[inline(always)]
+    fn para_encrypt<const NK: usize, const NM: usize>(keys: [Self; NK], blks: &mut [Block]) {
+        assert!(blks.len() >= NM * NK);
+        let mut ctxt = [Block::default(); NM];
+        keys.iter().enumerate().for_each(|(i, key)| {
+            ctxt.copy_from_slice(&blks[i * NM..(i + 1) * NM]);
+            blks[i * NM..(i + 1) * NM].copy_from_slice(&key.encrypt_many_blocks(ctxt))
+        });
+    }
+}
+
+/// The number of blocks buffered and encrypted together by the slice APIs built on `Aes`.
+pub(crate) const AES_BATCH: usize = 64;
+
+/// The number of blocks handled by one task when the parallel APIs split a slice across threads.
+pub(crate) const PAR_CHUNK: usize = 1 << 12;
+
 ///The AES 128 struct
 #[derive(Copy, Clone, Debug)]
 pub struct Aes([Block; 11]);
@@ -64,6 +121,9 @@ macro_rules! expand_assist_arm {
     };
 }
 
+mod aes256;
+pub use aes256::Aes256;
+
 impl Aes {
     // /// The AES_BLOCK_SIZE.
     // pub const AES_BLOCK_SIZE: usize = 8;
@@ -71,6 +131,10 @@ impl Aes {
     /// New an AES instance
     #[inline(always)]
     pub fn new(key: Block) -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return Self(soft::expand_key(key));
+        }
         unsafe { Aes::aes_init(key) }
     }
 
@@ -158,9 +222,98 @@ impl Aes {
         Self(kp)
     }
 
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn aes_init(key: Block) -> Self {
+        Self(soft::expand_key(key))
+    }
+
+    /// New `K` AES instances from `K` keys at once.\
+    /// The key schedules are interleaved so that the latency of key generation assists overlaps.
+    #[inline(always)]
+    pub fn para_new<const K: usize>(keys: [Block; K]) -> [Self; K] {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return keys.map(|k| Self(soft::expand_key(k)));
+        }
+        unsafe { Aes::para_aes_init(keys) }
+    }
+
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes")]
+    unsafe fn para_aes_init<const K: usize>(keys: [Block; K]) -> [Self; K] {
+        let mut kp = keys.map(|k| [k; 11]);
+
+        macro_rules! para_expand {
+            ($r:expr, $ac:expr) => {
+                for k in kp.iter_mut() {
+                    let mut x0 = k[$r - 1].0;
+                    let mut _x1 = _mm_setzero_si128();
+                    let mut x2 = _mm_setzero_si128();
+                    expand_assist_x86!(x0, _x1, x2, x0, 255, $ac);
+                    k[$r] = Block(x0);
+                }
+            };
+        }
+
+        para_expand!(1, 1);
+        para_expand!(2, 2);
+        para_expand!(3, 4);
+        para_expand!(4, 8);
+        para_expand!(5, 16);
+        para_expand!(6, 32);
+        para_expand!(7, 64);
+        para_expand!(8, 128);
+        para_expand!(9, 27);
+        para_expand!(10, 54);
+        kp.map(Self)
+    }
+
+    #[inline]
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "aes")]
+    unsafe fn para_aes_init<const K: usize>(keys: [Block; K]) -> [Self; K] {
+        let mut kp = keys.map(|k| [k; 11]);
+
+        macro_rules! para_expand {
+            ($r:expr, $ac:expr) => {
+                for k in kp.iter_mut() {
+                    let mut x0 = k[$r - 1].0;
+                    let mut _x1 = vdupq_n_u8(0);
+                    let mut x2 = vdupq_n_u8(0);
+                    expand_assist_arm!(x0, _x1, x2, x0, 255, $ac);
+                    k[$r] = Block(x0);
+                }
+            };
+        }
+
+        para_expand!(1, 1);
+        para_expand!(2, 2);
+        para_expand!(3, 4);
+        para_expand!(4, 8);
+        para_expand!(5, 16);
+        para_expand!(6, 32);
+        para_expand!(7, 64);
+        para_expand!(8, 128);
+        para_expand!(9, 27);
+        para_expand!(10, 54);
+        kp.map(Self)
+    }
+
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn para_aes_init<const K: usize>(keys: [Block; K]) -> [Self; K] {
+        keys.map(|k| Self(soft::expand_key(k)))
+    }
+
     /// Encrypt one block.
     #[inline(always)]
     pub fn encrypt_block(&self, blk: Block) -> Block {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return soft::encrypt(&self.0, blk);
+        }
         unsafe { self.encrypt_backend(blk) }
     }
 
@@ -192,9 +345,27 @@ impl Aes {
         Block(ctxt)
     }
 
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn encrypt_backend(&self, blk: Block) -> Block {
+        soft::encrypt(&self.0, blk)
+    }
+
     /// Encrypt many blocks
     #[inline(always)]
     pub fn encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return blks.map(|x| soft::encrypt(&self.0, x));
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if N >= 4 && CpuFeatures::get().vaes {
+            let mut blks = blks;
+            unsafe {
+                Aes::vaes_encrypt_backend(std::slice::from_ref(self), std::iter::once(N), &mut blks)
+            };
+            return blks;
+        }
         unsafe { self.unsafe_encrypt_many_blocks::<N>(blks) }
     }
 
@@ -217,7 +388,7 @@ impl Aes {
             *ct = _mm_aesenclast_si128(*ct, self.0[10].0);
         }
 
-        ctxt.map(|x| Block(x))
+        ctxt.map(Block)
     }
 
     #[inline]
@@ -239,14 +410,169 @@ impl Aes {
         ctxt.map(Block)
     }
 
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn unsafe_encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        blks.map(|x| soft::encrypt(&self.0, x))
+    }
+
+    // Encrypt blocks with VAES, 4 blocks per instruction.
+    // The blocks are divided into consecutive batches of lengths `lens`, the i-th batch is encrypted with `keys[i]`,
+    // and the remaining blocks are handled with AES-NI.
+    // Each of the 4 interleaved vectors keeps its round keys until its key changes.
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes,avx512f,vaes")]
+    unsafe fn vaes_encrypt_backend(
+        keys: &[Aes],
+        lens: impl Iterator<Item = usize>,
+        blks: &mut [Block],
+    ) {
+        let len = blks.len() / 4 * 4;
+        if keys.len() == 1 {
+            let rk = keys[0].0.map(|k| _mm512_broadcast_i32x4(k.0));
+            for chunk in blks[..len].chunks_mut(16) {
+                Aes::vaes_chunk_rounds([&rk; 4], chunk);
+            }
+            for blk in blks[len..].iter_mut() {
+                *blk = keys[0].encrypt_backend(*blk);
+            }
+            return;
+        }
+
+        let mut runs = KeyRuns::new(lens);
+        let mut round_keys = [[_mm512_setzero_si512(); 11]; 4];
+        let mut cached_index = [usize::MAX; 4];
+
+        let mut start = 0;
+        while start < len {
+            let m = ((len - start) / 4).min(4);
+            for (rk, cached) in round_keys.iter_mut().zip(cached_index.iter_mut()).take(m) {
+                if let Some(k) = runs.next_run(4) {
+                    if *cached != k {
+                        for (rk, k) in rk.iter_mut().zip(keys[k].0.iter()) {
+                            *rk = _mm512_broadcast_i32x4(k.0);
+                        }
+                        *cached = k;
+                    }
+                } else {
+                    let idx: [usize; 4] = std::array::from_fn(|_| runs.next_key());
+                    for (r, rk) in rk.iter_mut().enumerate() {
+                        let lanes = idx.map(|i| keys[i].0[r]);
+                        *rk = _mm512_loadu_si512(lanes.as_ptr() as *const _);
+                    }
+                    *cached = usize::MAX;
+                }
+            }
+
+            let [rk0, rk1, rk2, rk3] = &round_keys;
+            Aes::vaes_chunk_rounds([rk0, rk1, rk2, rk3], &mut blks[start..start + 4 * m]);
+            start += 4 * m;
+        }
+
+        for blk in blks[len..].iter_mut() {
+            *blk = keys[runs.next_key()].encrypt_backend(*blk);
+        }
+    }
+
+    // Run all AES rounds on a chunk of at most 16 blocks, i.e., at most 4 512-bit vectors.
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx512f,vaes")]
+    unsafe fn vaes_chunk_rounds(round_keys: [&[__m512i; 11]; 4], chunk: &mut [Block]) {
+        match chunk.len() / 4 {
+            1 => Aes::vaes_rounds::<1>(round_keys, chunk),
+            2 => Aes::vaes_rounds::<2>(round_keys, chunk),
+            3 => Aes::vaes_rounds::<3>(round_keys, chunk),
+            _ => Aes::vaes_rounds::<4>(round_keys, chunk),
+        }
+    }
+
+    // Run all AES rounds on `M` 512-bit vectors, interleaved to pipeline the instructions.
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx512f,vaes")]
+    unsafe fn vaes_rounds<const M: usize>(round_keys: [&[__m512i; 11]; 4], blks: &mut [Block]) {
+        let ptr = blks.as_mut_ptr() as *mut __m512i;
+        let mut ctxt = [_mm512_setzero_si512(); M];
+        for (j, ct) in ctxt.iter_mut().enumerate() {
+            *ct = _mm512_xor_si512(_mm512_loadu_si512(ptr.add(j) as *const _), round_keys[j][0]);
+        }
+
+        for r in 1..10 {
+            for (ct, rk) in ctxt.iter_mut().zip(round_keys.iter()) {
+                *ct = _mm512_aesenc_epi128(*ct, rk[r]);
+            }
+        }
+
+        for (j, (ct, rk)) in ctxt.iter().zip(round_keys.iter()).enumerate() {
+            let ct = _mm512_aesenclast_epi128(*ct, rk[10]);
+            _mm512_storeu_si512(ptr.add(j) as *mut _, ct);
+        }
+    }
+
     /// Encrypt block slice
     #[inline(always)]
     pub fn encrypt_block_slice(&self, blks: &mut [Block]) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            let features = CpuFeatures::get();
+            if features.vaes {
+                unsafe {
+                    Aes::vaes_encrypt_backend(
+                        std::slice::from_ref(self),
+                        std::iter::once(blks.len()),
+                        blks,
+                    )
+                };
+                return;
+            }
+            if !features.aes {
+                return self.encrypt_block_slice_soft(blks);
+            }
+        }
+        unsafe { self.encrypt_block_slice_simd(blks) }
+    }
+
+    /// Encrypt block slice with a given backend, which panics if the backend is not supported.
+    #[doc(hidden)]
+    pub fn encrypt_block_slice_with(&self, blks: &mut [Block], backend: Backend) {
+        assert!(CpuFeatures::get().supports_aes(backend));
+        match backend {
+            Backend::Soft => self.encrypt_block_slice_soft(blks),
+            Backend::Simd => unsafe { self.encrypt_block_slice_simd(blks) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Wide => unsafe {
+                Aes::vaes_encrypt_backend(
+                    std::slice::from_ref(self),
+                    std::iter::once(blks.len()),
+                    blks,
+                )
+            },
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::Wide => unreachable!(),
+        }
+    }
+
+    #[inline]
+    fn encrypt_block_slice_soft(&self, blks: &mut [Block]) {
+        for blk in blks.iter_mut() {
+            *blk = soft::encrypt(&self.0, *blk);
+        }
+    }
+
+    // Encrypt block slice with the 128-bit instructions, 8 blocks at a time.
+    #[inline]
+    #[cfg_attr(
+        any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"),
+        target_feature(enable = "aes")
+    )]
+    unsafe fn encrypt_block_slice_simd(&self, blks: &mut [Block]) {
         let len = blks.len();
         let mut buf = [Block::ZERO; 8];
         for i in 0..len / 8 {
             buf.copy_from_slice(&blks[i * 8..(i + 1) * 8]);
-            blks[i * 8..(i + 1) * 8].copy_from_slice(&self.encrypt_many_blocks(buf));
+            blks[i * 8..(i + 1) * 8].copy_from_slice(&self.unsafe_encrypt_many_blocks(buf));
         }
 
         let remain = len % 8;
@@ -256,7 +582,7 @@ impl Aes {
                     if remain == $n {
                         let mut buf = [Block::ZERO; $n];
                         buf.copy_from_slice(&blks[len - remain..]);
-                        blks[len - remain..].copy_from_slice(&self.encrypt_many_blocks(buf));
+                        blks[len - remain..].copy_from_slice(&self.unsafe_encrypt_many_blocks(buf));
                     }
                 }};
             }
@@ -277,12 +603,442 @@ impl Aes {
     #[inline(always)]
     pub fn para_encrypt<const NK: usize, const NM: usize>(keys: [Self; NK], blks: &mut [Block]) {
         assert!(blks.len() >= NM * NK);
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if NM * NK >= 4 && CpuFeatures::get().vaes {
+            unsafe { Aes::vaes_encrypt_backend(&keys, [NM; NK].into_iter(), &mut blks[..NM * NK]) };
+            return;
+        }
+
         let mut ctxt = [Block::default(); NM];
         keys.iter().enumerate().for_each(|(i, key)| {
             ctxt.copy_from_slice(&blks[i * NM..(i + 1) * NM]);
             blks[i * NM..(i + 1) * NM].copy_from_slice(&key.encrypt_many_blocks(ctxt))
         });
     }
+
+    /// Encrypt many blocks with many keys, where the number of keys is chosen at runtime.\
+    /// Input: AES keys `keys`, and blocks `blks` whose length is a multiple of `keys.len()`.\
+    /// Output: `blks` is divided into `keys.len()` consecutive batches of equal length,
+    /// and each batch is encrypted by the corresponding AES key.
+    #[inline(always)]
+    pub fn para_encrypt_slice(keys: &[Self], blks: &mut [Block]) {
+        if keys.is_empty() {
+            assert!(blks.is_empty());
+            return;
+        }
+        assert_eq!(blks.len() % keys.len(), 0);
+        let nm = blks.len() / keys.len();
+        Aes::multi_key_encrypt(keys, keys.iter().map(|_| nm), blks);
+    }
+
+    /// Encrypt many blocks with many keys and batches of different lengths.\
+    /// Input: AES keys `keys`, batch lengths `lens` with `lens.len() == keys.len()`,
+    /// and blocks `blks` with `blks.len() == lens.iter().sum()`.\
+    /// Output: `blks` is divided into consecutive batches of lengths `lens`,
+    /// and the i-th batch is encrypted by `keys[i]`.
+    #[inline(always)]
+    pub fn para_encrypt_groups(keys: &[Self], lens: &[usize], blks: &mut [Block]) {
+        assert_eq!(keys.len(), lens.len());
+        assert_eq!(blks.len(), lens.iter().sum::<usize>());
+        Aes::multi_key_encrypt(keys, lens.iter().copied(), blks);
+    }
+
+    // Encrypt consecutive batches of lengths `lens`, where the i-th batch is encrypted with `keys[i]`.
+    #[inline(always)]
+    fn multi_key_encrypt(keys: &[Aes], lens: impl Iterator<Item = usize>, blks: &mut [Block]) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            let mut runs = KeyRuns::new(lens);
+            for blk in blks.iter_mut() {
+                *blk = soft::encrypt(&keys[runs.next_key()].0, *blk);
+            }
+            return;
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if CpuFeatures::get().vaes {
+            unsafe { Aes::vaes_encrypt_backend(keys, lens, blks) };
+            return;
+        }
+        unsafe { Aes::multi_key_backend(keys, lens, blks) }
+    }
+
+    // Encrypt 8 blocks at a time with 8 possibly different keys, interleaving the rounds.
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes")]
+    unsafe fn multi_key_backend(
+        keys: &[Aes],
+        lens: impl Iterator<Item = usize>,
+        blks: &mut [Block],
+    ) {
+        let mut runs = KeyRuns::new(lens);
+        let mut chunks = blks.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            let ks: [&Aes; 8] = std::array::from_fn(|_| &keys[runs.next_key()]);
+            let mut ctxt = [_mm_setzero_si128(); 8];
+            for ((ct, blk), k) in ctxt.iter_mut().zip(chunk.iter()).zip(ks) {
+                *ct = _mm_xor_si128(blk.0, k.0[0].0);
+            }
+
+            for r in 1..10 {
+                for (ct, k) in ctxt.iter_mut().zip(ks) {
+                    *ct = _mm_aesenc_si128(*ct, k.0[r].0);
+                }
+            }
+
+            for ((blk, ct), k) in chunk.iter_mut().zip(ctxt).zip(ks) {
+                *blk = Block(_mm_aesenclast_si128(ct, k.0[10].0));
+            }
+        }
+
+        for blk in chunks.into_remainder() {
+            *blk = keys[runs.next_key()].encrypt_backend(*blk);
+        }
+    }
+
+    #[inline]
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "aes")]
+    unsafe fn multi_key_backend(
+        keys: &[Aes],
+        lens: impl Iterator<Item = usize>,
+        blks: &mut [Block],
+    ) {
+        let mut runs = KeyRuns::new(lens);
+        let mut chunks = blks.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            let ks: [&Aes; 8] = std::array::from_fn(|_| &keys[runs.next_key()]);
+            let mut ctxt = [vdupq_n_u8(0); 8];
+            for (ct, blk) in ctxt.iter_mut().zip(chunk.iter()) {
+                *ct = blk.0;
+            }
+
+            for r in 0..9 {
+                for (ct, k) in ctxt.iter_mut().zip(ks) {
+                    *ct = vaesmcq_u8(vaeseq_u8(*ct, k.0[r].0));
+                }
+            }
+
+            for ((blk, ct), k) in chunk.iter_mut().zip(ctxt).zip(ks) {
+                *blk = Block(veorq_u8(vaeseq_u8(ct, k.0[9].0), k.0[10].0));
+            }
+        }
+
+        for blk in chunks.into_remainder() {
+            *blk = keys[runs.next_key()].encrypt_backend(*blk);
+        }
+    }
+
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn multi_key_backend(
+        keys: &[Aes],
+        lens: impl Iterator<Item = usize>,
+        blks: &mut [Block],
+    ) {
+        let mut runs = KeyRuns::new(lens);
+        for blk in blks.iter_mut() {
+            *blk = soft::encrypt(&keys[runs.next_key()].0, *blk);
+        }
+    }
+
+    /// Get the decryption key schedule.\
+    /// Build it once and reuse it to decrypt many times, since it costs 9 `InvMixColumns` of the round keys.
+    #[inline(always)]
+    pub fn decryptor(&self) -> AesDec {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return AesDec(soft::inverse_keys(&self.0));
+        }
+        unsafe { self.decryptor_backend() }
+    }
+
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes")]
+    unsafe fn decryptor_backend(&self) -> AesDec {
+        let mut keys = [Block::ZERO; 11];
+        keys[0] = self.0[10];
+        for (k, key) in keys[1..10].iter_mut().zip(self.0[1..10].iter().rev()) {
+            *k = Block(_mm_aesimc_si128(key.0));
+        }
+        keys[10] = self.0[0];
+        AesDec(keys)
+    }
+
+    #[inline]
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "aes")]
+    unsafe fn decryptor_backend(&self) -> AesDec {
+        let mut keys = [Block::ZERO; 11];
+        keys[0] = self.0[10];
+        for (k, key) in keys[1..10].iter_mut().zip(self.0[1..10].iter().rev()) {
+            *k = Block(vaesimcq_u8(key.0));
+        }
+        keys[10] = self.0[0];
+        AesDec(keys)
+    }
+
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn decryptor_backend(&self) -> AesDec {
+        AesDec(soft::inverse_keys(&self.0))
+    }
+
+    /// Decrypt one block.\
+    /// It builds the decryption key schedule on every call, use `Aes::decryptor` to decrypt many times.
+    #[inline(always)]
+    pub fn decrypt_block(&self, blk: Block) -> Block {
+        self.decryptor().decrypt_block(blk)
+    }
+
+    /// Decrypt many blocks.\
+    /// It builds the decryption key schedule on every call, use `Aes::decryptor` to decrypt many times.
+    #[inline(always)]
+    pub fn decrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        self.decryptor().decrypt_many_blocks(blks)
+    }
+
+    /// Decrypt block slice.\
+    /// It builds the decryption key schedule on every call, use `Aes::decryptor` to decrypt many times.
+    #[inline(always)]
+    pub fn decrypt_block_slice(&self, blks: &mut [Block]) {
+        self.decryptor().decrypt_block_slice(blks);
+    }
+}
+
+/// The decryption key schedule of `Aes`, i.e., the round keys of the equivalent inverse cipher in FIPS-197.
+#[derive(Copy, Clone, Debug)]
+pub struct AesDec([Block; 11]);
+
+impl AesDec {
+    /// Decrypt one block.
+    #[inline(always)]
+    pub fn decrypt_block(&self, blk: Block) -> Block {
+        self.decrypt_many_blocks([blk])[0]
+    }
+
+    /// Decrypt many blocks.
+    #[inline(always)]
+    pub fn decrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return blks.map(|x| soft::decrypt(&self.0, x));
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if N >= 4 && CpuFeatures::get().vaes {
+            let mut blks = blks;
+            unsafe { self.vaes_decrypt_backend(&mut blks) };
+            return blks;
+        }
+        unsafe { self.unsafe_decrypt_many_blocks::<N>(blks) }
+    }
+
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes")]
+    unsafe fn unsafe_decrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        let mut ptxt = blks.map(|x| x.0);
+        for pt in ptxt.iter_mut() {
+            *pt = _mm_xor_si128(*pt, self.0[0].0);
+        }
+
+        for key in self.0[1..10].iter() {
+            for pt in ptxt.iter_mut() {
+                *pt = _mm_aesdec_si128(*pt, key.0);
+            }
+        }
+
+        for pt in ptxt.iter_mut() {
+            *pt = _mm_aesdeclast_si128(*pt, self.0[10].0);
+        }
+
+        ptxt.map(Block)
+    }
+
+    #[inline]
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "aes")]
+    unsafe fn unsafe_decrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        let mut ptxt = blks.map(|x| x.0);
+        for key in self.0[..9].iter() {
+            for pt in ptxt.iter_mut() {
+                *pt = vaesimcq_u8(vaesdq_u8(*pt, key.0));
+            }
+        }
+
+        for pt in ptxt.iter_mut() {
+            *pt = veorq_u8(vaesdq_u8(*pt, self.0[9].0), self.0[10].0);
+        }
+
+        ptxt.map(Block)
+    }
+
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn unsafe_decrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        blks.map(|x| soft::decrypt(&self.0, x))
+    }
+
+    // Decrypt blocks with VAES, 16 blocks at a time, and the remaining blocks with AES-NI.
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes,avx512f,vaes")]
+    unsafe fn vaes_decrypt_backend(&self, blks: &mut [Block]) {
+        let rk = self.0.map(|k| _mm512_broadcast_i32x4(k.0));
+        let len = blks.len() / 4 * 4;
+        for chunk in blks[..len].chunks_mut(16) {
+            match chunk.len() / 4 {
+                1 => AesDec::vaes_rounds::<1>(&rk, chunk),
+                2 => AesDec::vaes_rounds::<2>(&rk, chunk),
+                3 => AesDec::vaes_rounds::<3>(&rk, chunk),
+                _ => AesDec::vaes_rounds::<4>(&rk, chunk),
+            }
+        }
+        for blk in blks[len..].iter_mut() {
+            *blk = self.unsafe_decrypt_many_blocks([*blk])[0];
+        }
+    }
+
+    // Run all AES decryption rounds on `M` 512-bit vectors, interleaved to pipeline the instructions.
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx512f,vaes")]
+    unsafe fn vaes_rounds<const M: usize>(round_keys: &[__m512i; 11], blks: &mut [Block]) {
+        let ptr = blks.as_mut_ptr() as *mut __m512i;
+        let mut ptxt = [_mm512_setzero_si512(); M];
+        for (j, pt) in ptxt.iter_mut().enumerate() {
+            *pt = _mm512_xor_si512(_mm512_loadu_si512(ptr.add(j) as *const _), round_keys[0]);
+        }
+
+        for rk in round_keys[1..10].iter() {
+            for pt in ptxt.iter_mut() {
+                *pt = _mm512_aesdec_epi128(*pt, *rk);
+            }
+        }
+
+        for (j, pt) in ptxt.iter().enumerate() {
+            let pt = _mm512_aesdeclast_epi128(*pt, round_keys[10]);
+            _mm512_storeu_si512(ptr.add(j) as *mut _, pt);
+        }
+    }
+
+    /// Decrypt block slice.
+    #[inline(always)]
+    pub fn decrypt_block_slice(&self, blks: &mut [Block]) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if CpuFeatures::get().vaes {
+            unsafe { self.vaes_decrypt_backend(blks) };
+            return;
+        }
+
+        let len = blks.len();
+        let mut buf = [Block::ZERO; 8];
+        for i in 0..len / 8 {
+            buf.copy_from_slice(&blks[i * 8..(i + 1) * 8]);
+            blks[i * 8..(i + 1) * 8].copy_from_slice(&self.decrypt_many_blocks(buf));
+        }
+
+        let remain = len % 8;
+        if remain > 0 {
+            macro_rules! decrypt_some {
+                ($n:expr) => {{
+                    if remain == $n {
+                        let mut buf = [Block::ZERO; $n];
+                        buf.copy_from_slice(&blks[len - remain..]);
+                        blks[len - remain..].copy_from_slice(&self.decrypt_many_blocks(buf));
+                    }
+                }};
+            }
+            decrypt_some!(1);
+            decrypt_some!(2);
+            decrypt_some!(3);
+            decrypt_some!(4);
+            decrypt_some!(5);
+            decrypt_some!(6);
+            decrypt_some!(7);
+        }
+    }
+}
+
+// A cursor over consecutive batches of blocks, where the i-th batch of `lens[i]` blocks uses the i-th key.
+struct KeyRuns<I: Iterator<Item = usize>> {
+    lens: I,
+    key: usize,
+    remain: usize,
+}
+
+impl<I: Iterator<Item = usize>> KeyRuns<I> {
+    #[inline(always)]
+    fn new(mut lens: I) -> Self {
+        let remain = lens.next().unwrap_or(0);
+        Self {
+            lens,
+            key: 0,
+            remain,
+        }
+    }
+
+    #[inline(always)]
+    fn skip_empty(&mut self) {
+        while self.remain == 0 {
+            self.key += 1;
+            self.remain = self.lens.next().expect("more blocks than the batches");
+        }
+    }
+
+    // The key index of the next block.
+    #[inline(always)]
+    fn next_key(&mut self) -> usize {
+        self.skip_empty();
+        self.remain -= 1;
+        self.key
+    }
+
+    // The key index of the next `n` blocks if they are in the same batch.
+    #[cfg_attr(
+        not(any(target_arch = "x86", target_arch = "x86_64")),
+        allow(dead_code)
+    )]
+    #[inline(always)]
+    fn next_run(&mut self, n: usize) -> Option<usize> {
+        self.skip_empty();
+        if self.remain >= n {
+            self.remain -= n;
+            Some(self.key)
+        } else {
+            None
+        }
+    }
+}
+
+impl BlockCipher for Aes {
+    type Key = Block;
+
+    #[inline(always)]
+    fn new(key: Self::Key) -> Self {
+        Aes::new(key)
+    }
+
+    #[inline(always)]
+    fn encrypt_block(&self, blk: Block) -> Block {
+        Aes::encrypt_block(self, blk)
+    }
+
+    #[inline(always)]
+    fn encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        Aes::encrypt_many_blocks(self, blks)
+    }
+
+    #[inline(always)]
+    fn encrypt_block_slice(&self, blks: &mut [Block]) {
+        Aes::encrypt_block_slice(self, blks)
+    }
+
+    #[inline(always)]
+    fn para_encrypt<const NK: usize, const NM: usize>(keys: [Self; NK], blks: &mut [Block]) {
+        Aes::para_encrypt::<NK, NM>(keys, blks)
+    }
 }
 
 #[test]
@@ -329,3 +1085,174 @@ fn aes_test() {
         ]
     );
 }
+
+#[test]
+fn aes_soft_test() {
+    let key = rand::random::<Block>();
+    let aes = Aes::new(key);
+    assert_eq!(aes.0, soft::expand_key(key));
+
+    let blks = rand::random::<[Block; 8]>();
+    let res = aes.encrypt_many_blocks(blks);
+    for (x, y) in blks.iter().zip(res.iter()) {
+        assert_eq!(aes.encrypt_block(*x), *y);
+        assert_eq!(soft::encrypt(&aes.0, *x), *y);
+    }
+}
+
+#[test]
+fn aes_slice_test() {
+    let keys = rand::random::<[Block; 3]>().map(Aes::new);
+
+    for len in [1, 3, 4, 5, 8, 13, 16, 17, 35] {
+        let blks: Vec<Block> = (0..len).map(|_| rand::random()).collect();
+        let mut res = blks.clone();
+        keys[0].encrypt_block_slice(&mut res);
+        for (x, y) in blks.iter().zip(res.iter()) {
+            assert_eq!(keys[0].encrypt_block(*x), *y);
+        }
+
+        let features = CpuFeatures::get();
+        for backend in [Backend::Soft, Backend::Simd, Backend::Wide] {
+            if features.supports_aes(backend) {
+                let mut x = blks.clone();
+                keys[0].encrypt_block_slice_with(&mut x, backend);
+                assert_eq!(x, res);
+            }
+        }
+    }
+
+    let blks = rand::random::<[Block; 15]>();
+    let mut res = blks;
+    Aes::para_encrypt::<3, 5>(keys, &mut res);
+    for (i, (x, y)) in blks.iter().zip(res.iter()).enumerate() {
+        assert_eq!(keys[i / 5].encrypt_block(*x), *y);
+    }
+}
+
+#[test]
+fn aes_decrypt_test() {
+    // FIPS-197, Appendix B and Appendix C.1.
+    let vectors = [
+        (
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "3243f6a8885a308d313198a2e0370734",
+            "3925841d02dc09fbdc118597196a0b32",
+        ),
+        (
+            "000102030405060708090a0b0c0d0e0f",
+            "00112233445566778899aabbccddeeff",
+            "69c4e0d86a7b0430d8cdb78070b4c55a",
+        ),
+    ];
+    let from_hex = |s: &str| {
+        let mut bytes = [0u8; 16];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        Block::new(&bytes)
+    };
+
+    for (key, ptxt, ctxt) in vectors {
+        let aes = Aes::new(from_hex(key));
+        let ptxt = from_hex(ptxt);
+        let ctxt = from_hex(ctxt);
+        assert_eq!(aes.encrypt_block(ptxt), ctxt);
+        assert_eq!(aes.decrypt_block(ctxt), ptxt);
+        assert_eq!(soft::decrypt(&soft::inverse_keys(&aes.0), ctxt), ptxt);
+        assert_eq!(aes.decryptor().0, soft::inverse_keys(&aes.0));
+    }
+
+    let aes = Aes::new(rand::random::<Block>());
+    for len in [1, 7, 8, 9, 20] {
+        let blks: Vec<Block> = (0..len).map(|_| rand::random()).collect();
+        let mut res = blks.clone();
+        aes.encrypt_block_slice(&mut res);
+        aes.decrypt_block_slice(&mut res);
+        assert_eq!(blks, res);
+    }
+
+    let blks = rand::random::<[Block; 4]>();
+    assert_eq!(aes.decrypt_many_blocks(aes.encrypt_many_blocks(blks)), blks);
+
+    let dec = aes.decryptor();
+    for len in [1, 4, 15, 16, 17, 36] {
+        let blks: Vec<Block> = (0..len).map(|_| rand::random()).collect();
+        let mut res = blks.clone();
+        aes.encrypt_block_slice(&mut res);
+        dec.decrypt_block_slice(&mut res);
+        assert_eq!(blks, res);
+    }
+    let blks = rand::random::<[Block; 9]>();
+    assert_eq!(dec.decrypt_many_blocks(aes.encrypt_many_blocks(blks)), blks);
+}
+
+#[test]
+fn aes_para_encrypt_slice_test() {
+    let keys = Aes::para_new(rand::random::<[Block; 5]>());
+    for nm in [0, 1, 2, 3, 7, 8, 9] {
+        let blks: Vec<Block> = (0..5 * nm).map(|_| rand::random::<Block>()).collect();
+        let mut x = blks.clone();
+        Aes::para_encrypt_slice(&keys, &mut x);
+        for (i, (x, y)) in x.iter().zip(blks.iter()).enumerate() {
+            assert_eq!(*x, keys[i / nm].encrypt_block(*y));
+        }
+    }
+
+    let mut x = rand::random::<[Block; 15]>();
+    let mut y = x;
+    Aes::para_encrypt_slice(&keys, &mut x);
+    Aes::para_encrypt::<5, 3>(keys, &mut y);
+    assert_eq!(x, y);
+
+    let lens = [3, 0, 9, 1, 6];
+    let blks = rand::random::<[Block; 19]>();
+    let mut x = blks;
+    Aes::para_encrypt_groups(&keys, &lens, &mut x);
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if CpuFeatures::get().aes {
+        let mut y = blks;
+        unsafe { Aes::multi_key_backend(&keys, lens.into_iter(), &mut y) };
+        assert_eq!(x, y);
+    }
+    let mut start = 0;
+    for (key, len) in keys.iter().zip(lens) {
+        for i in start..start + len {
+            assert_eq!(x[i], key.encrypt_block(blks[i]));
+        }
+        start += len;
+    }
+}
+
+#[test]
+fn aes_para_new_test() {
+    let keys = rand::random::<[Block; 8]>();
+    let aes = Aes::para_new(keys);
+    for (x, k) in aes.iter().zip(keys) {
+        assert_eq!(x.0, Aes::new(k).0);
+        assert_eq!(x.0, soft::expand_key(k));
+    }
+}
+
+#[test]
+fn block_cipher_test() {
+    fn encrypt_all<C: BlockCipher>(key: C::Key, blks: &mut [Block]) {
+        C::new(key).encrypt_block_slice(blks);
+    }
+
+    let key = rand::random::<Block>();
+    let blks = rand::random::<[Block; 10]>();
+    let mut x = blks;
+    let mut y = blks;
+    encrypt_all::<Aes>(key, &mut x);
+    Aes::new(key).encrypt_block_slice(&mut y);
+    assert_eq!(x, y);
+
+    let key = rand::random::<[Block; 2]>();
+    let aes = Aes256::new(key);
+    let mut x = blks;
+    encrypt_all::<Aes256>(key, &mut x);
+    for (x, y) in x.iter().zip(blks.iter()) {
+        assert_eq!(*x, aes.encrypt_block(*y));
+    }
+}
@@ -0,0 +1,276 @@
+//! Implement aes256
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+#[cfg(target_arch = "aarch64")]
+use crate::sse2neon::AES_SBOX;
+
+#[cfg(target_arch = "aarch64")]
+use crate::{
+    _mm_aeskeygenassist_si128, _mm_castps_si128, _mm_castsi128_ps, _mm_cvtsi128_si32,
+    _mm_shuffle_epi32, _mm_shuffle_ps, _mm_xor_si128,
+};
+
+#[cfg(not(target_arch = "aarch64"))]
+use super::soft;
+use super::BlockCipher;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::cpu_features::CpuFeatures;
+use crate::Block;
+
+///The AES 256 struct
+#[derive(Copy, Clone, Debug)]
+pub struct Aes256([Block; 15]);
+
+impl Aes256 {
+    /// New an AES256 instance with a 256-bit key `[low, high]`.
+    #[inline(always)]
+    pub fn new(key: [Block; 2]) -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return Self(soft::expand_key_256(key));
+        }
+        unsafe { Aes256::aes_init(key) }
+    }
+
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes")]
+    unsafe fn aes_init(key: [Block; 2]) -> Self {
+        let mut kp = [Block::default(); 15];
+        kp[0] = key[0];
+        kp[1] = key[1];
+        let mut x0 = key[0].0;
+        let mut x1 = key[1].0;
+        let mut _x2 = _mm_setzero_si128();
+        let mut x3 = _mm_setzero_si128();
+
+        expand_assist_x86!(x0, _x2, x3, x1, 255, 1);
+        kp[2] = Block(x0);
+        expand_assist_x86!(x1, _x2, x3, x0, 170, 0);
+        kp[3] = Block(x1);
+
+        expand_assist_x86!(x0, _x2, x3, x1, 255, 2);
+        kp[4] = Block(x0);
+        expand_assist_x86!(x1, _x2, x3, x0, 170, 0);
+        kp[5] = Block(x1);
+
+        expand_assist_x86!(x0, _x2, x3, x1, 255, 4);
+        kp[6] = Block(x0);
+        expand_assist_x86!(x1, _x2, x3, x0, 170, 0);
+        kp[7] = Block(x1);
+
+        expand_assist_x86!(x0, _x2, x3, x1, 255, 8);
+        kp[8] = Block(x0);
+        expand_assist_x86!(x1, _x2, x3, x0, 170, 0);
+        kp[9] = Block(x1);
+
+        expand_assist_x86!(x0, _x2, x3, x1, 255, 16);
+        kp[10] = Block(x0);
+        expand_assist_x86!(x1, _x2, x3, x0, 170, 0);
+        kp[11] = Block(x1);
+
+        expand_assist_x86!(x0, _x2, x3, x1, 255, 32);
+        kp[12] = Block(x0);
+        expand_assist_x86!(x1, _x2, x3, x0, 170, 0);
+        kp[13] = Block(x1);
+
+        expand_assist_x86!(x0, _x2, x3, x1, 255, 64);
+        kp[14] = Block(x0);
+        Self(kp)
+    }
+
+    #[inline]
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "aes")]
+    unsafe fn aes_init(key: [Block; 2]) -> Self {
+        let mut kp = [Block::default(); 15];
+        kp[0] = key[0];
+        kp[1] = key[1];
+        let mut x0 = key[0].0;
+        let mut x1 = key[1].0;
+        let mut _x2 = vdupq_n_u8(0);
+        let mut x3 = vdupq_n_u8(0);
+
+        expand_assist_arm!(x0, _x2, x3, x1, 255, 1);
+        kp[2] = Block(x0);
+        expand_assist_arm!(x1, _x2, x3, x0, 170, 0);
+        kp[3] = Block(x1);
+
+        expand_assist_arm!(x0, _x2, x3, x1, 255, 2);
+        kp[4] = Block(x0);
+        expand_assist_arm!(x1, _x2, x3, x0, 170, 0);
+        kp[5] = Block(x1);
+
+        expand_assist_arm!(x0, _x2, x3, x1, 255, 4);
+        kp[6] = Block(x0);
+        expand_assist_arm!(x1, _x2, x3, x0, 170, 0);
+        kp[7] = Block(x1);
+
+        expand_assist_arm!(x0, _x2, x3, x1, 255, 8);
+        kp[8] = Block(x0);
+        expand_assist_arm!(x1, _x2, x3, x0, 170, 0);
+        kp[9] = Block(x1);
+
+        expand_assist_arm!(x0, _x2, x3, x1, 255, 16);
+        kp[10] = Block(x0);
+        expand_assist_arm!(x1, _x2, x3, x0, 170, 0);
+        kp[11] = Block(x1);
+
+        expand_assist_arm!(x0, _x2, x3, x1, 255, 32);
+        kp[12] = Block(x0);
+        expand_assist_arm!(x1, _x2, x3, x0, 170, 0);
+        kp[13] = Block(x1);
+
+        expand_assist_arm!(x0, _x2, x3, x1, 255, 64);
+        kp[14] = Block(x0);
+        Self(kp)
+    }
+
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn aes_init(key: [Block; 2]) -> Self {
+        Self(soft::expand_key_256(key))
+    }
+
+    /// Encrypt one block.
+    #[inline(always)]
+    pub fn encrypt_block(&self, blk: Block) -> Block {
+        self.encrypt_many_blocks([blk])[0]
+    }
+
+    /// Encrypt many blocks
+    #[inline(always)]
+    pub fn encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().aes {
+            return blks.map(|x| soft::encrypt(&self.0, x));
+        }
+        unsafe { self.unsafe_encrypt_many_blocks::<N>(blks) }
+    }
+
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "aes")]
+    unsafe fn unsafe_encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        let mut ctxt = blks.map(|x| x.0);
+        for ct in ctxt.iter_mut() {
+            *ct = _mm_xor_si128(*ct, self.0[0].0);
+        }
+
+        for key in self.0[1..14].iter() {
+            for ct in ctxt.iter_mut() {
+                *ct = _mm_aesenc_si128(*ct, key.0);
+            }
+        }
+
+        for ct in ctxt.iter_mut() {
+            *ct = _mm_aesenclast_si128(*ct, self.0[14].0);
+        }
+
+        ctxt.map(Block)
+    }
+
+    #[inline]
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "aes")]
+    unsafe fn unsafe_encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        let mut ctxt = blks.map(|x| x.0);
+
+        for key in self.0.iter().take(13) {
+            for ct in ctxt.iter_mut() {
+                *ct = vaesmcq_u8(vaeseq_u8(*ct, key.0));
+            }
+        }
+
+        for ct in ctxt.iter_mut() {
+            *ct = veorq_u8(vaeseq_u8(*ct, self.0[13].0), self.0[14].0);
+        }
+
+        ctxt.map(Block)
+    }
+
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn unsafe_encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        blks.map(|x| soft::encrypt(&self.0, x))
+    }
+
+    /// Encrypt block slice
+    #[inline(always)]
+    pub fn encrypt_block_slice(&self, blks: &mut [Block]) {
+        BlockCipher::encrypt_block_slice(self, blks);
+    }
+
+    /// Encrypt many blocks with many keys.
+    /// Input: `NK` AES keys `keys`, and `NK * NM` blocks `blks`
+    /// Output: each batch of NM blocks encrypted by a corresponding AES key.
+    /// Only handle the first `NK * NM` blocks of blks, do not handle the rest.
+    #[inline(always)]
+    pub fn para_encrypt<const NK: usize, const NM: usize>(keys: [Self; NK], blks: &mut [Block]) {
+        <Aes256 as BlockCipher>::para_encrypt::<NK, NM>(keys, blks);
+    }
+}
+
+impl BlockCipher for Aes256 {
+    type Key = [Block; 2];
+
+    #[inline(always)]
+    fn new(key: Self::Key) -> Self {
+        Aes256::new(key)
+    }
+
+    #[inline(always)]
+    fn encrypt_block(&self, blk: Block) -> Block {
+        Aes256::encrypt_block(self, blk)
+    }
+
+    #[inline(always)]
+    fn encrypt_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        Aes256::encrypt_many_blocks(self, blks)
+    }
+}
+
+#[test]
+fn aes256_test() {
+    // FIPS-197, Appendix C.3.
+    let key = [
+        Block::new(&[
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ]),
+        Block::new(&[
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+            0x1e, 0x1f,
+        ]),
+    ];
+    let ptxt = Block::new(&[
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ]);
+    let ctxt = Block::new(&[
+        0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49, 0x60,
+        0x89,
+    ]);
+
+    let aes = Aes256::new(key);
+    assert_eq!(aes.0, super::soft::expand_key_256(key));
+    assert_eq!(aes.encrypt_block(ptxt), ctxt);
+
+    let mut blks = [ptxt; 11];
+    aes.encrypt_block_slice(&mut blks);
+    assert_eq!(blks, [ctxt; 11]);
+    assert_eq!(aes.encrypt_many_blocks([ptxt; 3]), [ctxt; 3]);
+
+    let keys = [aes, Aes256::new(rand::random::<[Block; 2]>())];
+    let mut blks = [ptxt; 6];
+    Aes256::para_encrypt::<2, 3>(keys, &mut blks);
+    assert_eq!(blks[..3], [ctxt; 3]);
+    assert_eq!(blks[3], keys[1].encrypt_block(ptxt));
+}
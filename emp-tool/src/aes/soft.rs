@@ -0,0 +1,514 @@
+//! Constant-time bitsliced software implementation of AES-128/AES-256 and the inverse ciphers.
+//! It is used when AES instructions are not available on the target.
+//!
+//! The 16 bytes of a state are stored as 8 bit planes: bit `i` of plane `j` is bit `j` of byte `i`.
+//! Bytes are numbered in the AES column-major order, i.e., byte `i` is in row `i % 4` and column `i / 4`.
+//! The S-box is evaluated with the circuit of Boyar and Peralta (<https://eprint.iacr.org/2011/332>).
+
+use crate::Block;
+
+/// Bitsliced AES state.
+type State = [u16; 8];
+
+/// Round constants of the AES key schedules.
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+// Transpose an 8x8 bit matrix, where the i-th byte is the i-th row.
+#[inline(always)]
+fn transpose8(mut x: u64) -> u64 {
+    let t = (x ^ (x >> 7)) & 0x00AA_00AA_00AA_00AA;
+    x ^= t ^ (t << 7);
+    let t = (x ^ (x >> 14)) & 0x0000_CCCC_0000_CCCC;
+    x ^= t ^ (t << 14);
+    let t = (x ^ (x >> 28)) & 0x0000_0000_F0F0_F0F0;
+    x ^= t ^ (t << 28);
+    x
+}
+
+// Convert a block into bit planes.
+#[inline(always)]
+fn bitslice(blk: Block) -> State {
+    let x = u128::from(blk);
+    let lo = transpose8(x as u64);
+    let hi = transpose8((x >> 64) as u64);
+    let mut q = [0u16; 8];
+    for (j, q) in q.iter_mut().enumerate() {
+        *q = ((lo >> (8 * j)) & 0xFF) as u16 | ((((hi >> (8 * j)) & 0xFF) as u16) << 8);
+    }
+    q
+}
+
+// Convert bit planes back into a block.
+#[inline(always)]
+fn unbitslice(q: &State) -> Block {
+    let mut lo = 0u64;
+    let mut hi = 0u64;
+    for (j, q) in q.iter().enumerate() {
+        lo |= ((*q & 0xFF) as u64) << (8 * j);
+        hi |= ((*q >> 8) as u64) << (8 * j);
+    }
+    Block::from(transpose8(lo) as u128 | ((transpose8(hi) as u128) << 64))
+}
+
+// Apply the AES S-box to all the bytes in bit planes.
+#[inline(always)]
+fn sub_bytes(q: &mut State) {
+    let x0 = q[7];
+    let x1 = q[6];
+    let x2 = q[5];
+    let x3 = q[4];
+    let x4 = q[3];
+    let x5 = q[2];
+    let x6 = q[1];
+    let x7 = q[0];
+
+    // Top linear transformation.
+    let y14 = x3 ^ x5;
+    let y13 = x0 ^ x6;
+    let y9 = x0 ^ x3;
+    let y8 = x0 ^ x5;
+    let t0 = x1 ^ x2;
+    let y1 = t0 ^ x7;
+    let y4 = y1 ^ x3;
+    let y12 = y13 ^ y14;
+    let y2 = y1 ^ x0;
+    let y5 = y1 ^ x6;
+    let y3 = y5 ^ y8;
+    let t1 = x4 ^ y12;
+    let y15 = t1 ^ x5;
+    let y20 = t1 ^ x1;
+    let y6 = y15 ^ x7;
+    let y10 = y15 ^ t0;
+    let y11 = y20 ^ y9;
+    let y7 = x7 ^ y11;
+    let y17 = y10 ^ y11;
+    let y19 = y10 ^ y8;
+    let y16 = t0 ^ y11;
+    let y21 = y13 ^ y16;
+    let y18 = x0 ^ y16;
+
+    // Non-linear section.
+    let t2 = y12 & y15;
+    let t3 = y3 & y6;
+    let t4 = t3 ^ t2;
+    let t5 = y4 & x7;
+    let t6 = t5 ^ t2;
+    let t7 = y13 & y16;
+    let t8 = y5 & y1;
+    let t9 = t8 ^ t7;
+    let t10 = y2 & y7;
+    let t11 = t10 ^ t7;
+    let t12 = y9 & y11;
+    let t13 = y14 & y17;
+    let t14 = t13 ^ t12;
+    let t15 = y8 & y10;
+    let t16 = t15 ^ t12;
+    let t17 = t4 ^ t14;
+    let t18 = t6 ^ t16;
+    let t19 = t9 ^ t14;
+    let t20 = t11 ^ t16;
+    let t21 = t17 ^ y20;
+    let t22 = t18 ^ y19;
+    let t23 = t19 ^ y21;
+    let t24 = t20 ^ y18;
+
+    let t25 = t21 ^ t22;
+    let t26 = t21 & t23;
+    let t27 = t24 ^ t26;
+    let t28 = t25 & t27;
+    let t29 = t28 ^ t22;
+    let t30 = t23 ^ t24;
+    let t31 = t22 ^ t26;
+    let t32 = t31 & t30;
+    let t33 = t32 ^ t24;
+    let t34 = t23 ^ t33;
+    let t35 = t27 ^ t33;
+    let t36 = t24 & t35;
+    let t37 = t36 ^ t34;
+    let t38 = t27 ^ t36;
+    let t39 = t29 & t38;
+    let t40 = t25 ^ t39;
+
+    let t41 = t40 ^ t37;
+    let t42 = t29 ^ t33;
+    let t43 = t29 ^ t40;
+    let t44 = t33 ^ t37;
+    let t45 = t42 ^ t41;
+    let z0 = t44 & y15;
+    let z1 = t37 & y6;
+    let z2 = t33 & x7;
+    let z3 = t43 & y16;
+    let z4 = t40 & y1;
+    let z5 = t29 & y7;
+    let z6 = t42 & y11;
+    let z7 = t45 & y17;
+    let z8 = t41 & y10;
+    let z9 = t44 & y12;
+    let z10 = t37 & y3;
+    let z11 = t33 & y4;
+    let z12 = t43 & y13;
+    let z13 = t40 & y5;
+    let z14 = t29 & y2;
+    let z15 = t42 & y9;
+    let z16 = t45 & y14;
+    let z17 = t41 & y8;
+
+    // Bottom linear transformation.
+    let t46 = z15 ^ z16;
+    let t47 = z10 ^ z11;
+    let t48 = z5 ^ z13;
+    let t49 = z9 ^ z10;
+    let t50 = z2 ^ z12;
+    let t51 = z2 ^ z5;
+    let t52 = z7 ^ z8;
+    let t53 = z0 ^ z3;
+    let t54 = z6 ^ z7;
+    let t55 = z16 ^ z17;
+    let t56 = z12 ^ t48;
+    let t57 = t50 ^ t53;
+    let t58 = z4 ^ t46;
+    let t59 = z3 ^ t54;
+    let t60 = t46 ^ t57;
+    let t61 = z14 ^ t57;
+    let t62 = t52 ^ t58;
+    let t63 = t49 ^ t58;
+    let t64 = z4 ^ t59;
+    let t65 = t61 ^ t62;
+    let t66 = z1 ^ t63;
+    let s0 = t59 ^ t63;
+    let s6 = t56 ^ !t62;
+    let s7 = t48 ^ !t60;
+    let t67 = t64 ^ t65;
+    let s3 = t53 ^ t66;
+    let s4 = t51 ^ t66;
+    let s5 = t47 ^ t65;
+    let s1 = t64 ^ !s3;
+    let s2 = t55 ^ !t67;
+
+    q[7] = s0;
+    q[6] = s1;
+    q[5] = s2;
+    q[4] = s3;
+    q[3] = s4;
+    q[2] = s5;
+    q[1] = s6;
+    q[0] = s7;
+}
+
+// The inverse of the affine transformation of the S-box, with the constant folded in.
+#[inline(always)]
+fn inv_affine(q: &mut State) {
+    let q0 = !q[0];
+    let q1 = !q[1];
+    let q2 = q[2];
+    let q3 = q[3];
+    let q4 = q[4];
+    let q5 = !q[5];
+    let q6 = !q[6];
+    let q7 = q[7];
+    q[7] = q1 ^ q4 ^ q6;
+    q[6] = q0 ^ q3 ^ q5;
+    q[5] = q7 ^ q2 ^ q4;
+    q[4] = q6 ^ q1 ^ q3;
+    q[3] = q5 ^ q0 ^ q2;
+    q[2] = q4 ^ q7 ^ q1;
+    q[1] = q3 ^ q6 ^ q0;
+    q[0] = q2 ^ q5 ^ q7;
+}
+
+// Apply the inverse AES S-box to all the bytes in bit planes.
+// Since `S(x) = A(x^-1)` for the affine map `A`, we have `S^-1(y) = A^-1(S(A^-1(y)))`.
+#[inline(always)]
+fn inv_sub_bytes(q: &mut State) {
+    inv_affine(q);
+    sub_bytes(q);
+    inv_affine(q);
+}
+
+// Rotate every column of a plane upwards by `K` rows.
+#[inline(always)]
+fn rotate_rows<const K: u32>(x: u16) -> u16 {
+    let lo = 0x1111u16 * ((1 << (4 - K)) - 1);
+    ((x >> K) & lo) | ((x << (4 - K)) & !lo)
+}
+
+// Shift the r-th row of every plane to the left by r positions.
+#[inline(always)]
+fn shift_rows(q: &mut State) {
+    for x in q.iter_mut() {
+        *x = (*x & 0x1111)
+            | (*x & 0x2222).rotate_right(4)
+            | (*x & 0x4444).rotate_right(8)
+            | (*x & 0x8888).rotate_right(12);
+    }
+}
+
+// Shift the r-th row of every plane to the right by r positions.
+#[inline(always)]
+fn inv_shift_rows(q: &mut State) {
+    for x in q.iter_mut() {
+        *x = (*x & 0x1111)
+            | (*x & 0x2222).rotate_left(4)
+            | (*x & 0x4444).rotate_left(8)
+            | (*x & 0x8888).rotate_left(12);
+    }
+}
+
+// Multiply `t` by `x` in GF(2^8).
+#[inline(always)]
+fn xtime(t: &State) -> State {
+    [
+        t[7],
+        t[0] ^ t[7],
+        t[1],
+        t[2] ^ t[7],
+        t[3] ^ t[7],
+        t[4],
+        t[5],
+        t[6],
+    ]
+}
+
+// Multiply each column by the fixed MixColumns polynomial.
+#[inline(always)]
+fn mix_columns(q: &mut State) {
+    let r1 = q.map(rotate_rows::<1>);
+    let r2 = q.map(rotate_rows::<2>);
+    let r3 = q.map(rotate_rows::<3>);
+
+    let mut t = [0u16; 8];
+    for j in 0..8 {
+        t[j] = q[j] ^ r1[j];
+    }
+
+    let xt = xtime(&t);
+
+    for j in 0..8 {
+        q[j] = xt[j] ^ r1[j] ^ r2[j] ^ r3[j];
+    }
+}
+
+// Multiply each column by the inverse of the MixColumns polynomial.
+// It uses `{0e, 0b, 0d, 09} = {02, 03, 01, 01} * {05, 00, 04, 00}`.
+#[inline(always)]
+fn inv_mix_columns(q: &mut State) {
+    let r2 = q.map(rotate_rows::<2>);
+
+    let mut t = [0u16; 8];
+    for j in 0..8 {
+        t[j] = q[j] ^ r2[j];
+    }
+
+    let t = xtime(&xtime(&t));
+    for j in 0..8 {
+        q[j] ^= t[j];
+    }
+    mix_columns(q);
+}
+
+#[inline(always)]
+fn add_round_key(q: &mut State, key: &State) {
+    for (x, k) in q.iter_mut().zip(key.iter()) {
+        *x ^= k;
+    }
+}
+
+// Apply the S-box to each byte of a word.
+#[inline(always)]
+fn sub_word(w: u32) -> u32 {
+    let mut q = bitslice(Block::from(w as u128));
+    sub_bytes(&mut q);
+    u128::from(unbitslice(&q)) as u32
+}
+
+// Pack round key words into blocks.
+#[inline(always)]
+fn words_to_blocks<const NR: usize>(w: &[u32]) -> [Block; NR] {
+    let mut kp = [Block::ZERO; NR];
+    for (k, w) in kp.iter_mut().zip(w.chunks_exact(4)) {
+        *k = Block::from(
+            w[0] as u128 | (w[1] as u128) << 32 | (w[2] as u128) << 64 | (w[3] as u128) << 96,
+        );
+    }
+    kp
+}
+
+/// Expand an AES-128 key into 11 round keys.
+pub(crate) fn expand_key(key: Block) -> [Block; 11] {
+    let mut w = [0u32; 44];
+    let key = u128::from(key);
+    for (i, w) in w.iter_mut().take(4).enumerate() {
+        *w = (key >> (32 * i)) as u32;
+    }
+
+    for i in 4..44 {
+        let mut t = w[i - 1];
+        if i % 4 == 0 {
+            t = sub_word(t.rotate_right(8)) ^ RCON[i / 4 - 1] as u32;
+        }
+        w[i] = w[i - 4] ^ t;
+    }
+    words_to_blocks(&w)
+}
+
+/// Expand an AES-256 key into 15 round keys.
+pub(crate) fn expand_key_256(key: [Block; 2]) -> [Block; 15] {
+    let mut w = [0u32; 60];
+    for (i, w) in w.iter_mut().take(8).enumerate() {
+        *w = (u128::from(key[i / 4]) >> (32 * (i % 4))) as u32;
+    }
+
+    for i in 8..60 {
+        let mut t = w[i - 1];
+        if i % 8 == 0 {
+            t = sub_word(t.rotate_right(8)) ^ RCON[i / 8 - 1] as u32;
+        } else if i % 8 == 4 {
+            t = sub_word(t);
+        }
+        w[i] = w[i - 8] ^ t;
+    }
+    words_to_blocks(&w)
+}
+
+/// Encrypt one block with the expanded round keys.
+/// The number of rounds is `keys.len() - 1`.
+pub(crate) fn encrypt(keys: &[Block], blk: Block) -> Block {
+    let nr = keys.len() - 1;
+    let mut q = bitslice(blk);
+    add_round_key(&mut q, &bitslice(keys[0]));
+
+    for key in keys[1..nr].iter() {
+        sub_bytes(&mut q);
+        shift_rows(&mut q);
+        mix_columns(&mut q);
+        add_round_key(&mut q, &bitslice(*key));
+    }
+
+    sub_bytes(&mut q);
+    shift_rows(&mut q);
+    add_round_key(&mut q, &bitslice(keys[nr]));
+    unbitslice(&q)
+}
+
+/// Derive the round keys of the equivalent inverse cipher in FIPS-197 from the round keys of encryption.
+pub(crate) fn inverse_keys<const N: usize>(keys: &[Block; N]) -> [Block; N] {
+    let mut res = [Block::ZERO; N];
+    res[0] = keys[N - 1];
+    for (r, key) in res[1..N - 1].iter_mut().zip(keys[1..N - 1].iter().rev()) {
+        let mut q = bitslice(*key);
+        inv_mix_columns(&mut q);
+        *r = unbitslice(&q);
+    }
+    res[N - 1] = keys[0];
+    res
+}
+
+/// Decrypt one block with the round keys of the equivalent inverse cipher from `inverse_keys`.
+/// The number of rounds is `keys.len() - 1`.
+pub(crate) fn decrypt(keys: &[Block], blk: Block) -> Block {
+    let nr = keys.len() - 1;
+    let mut q = bitslice(blk);
+    add_round_key(&mut q, &bitslice(keys[0]));
+
+    for key in keys[1..nr].iter() {
+        inv_shift_rows(&mut q);
+        inv_sub_bytes(&mut q);
+        inv_mix_columns(&mut q);
+        add_round_key(&mut q, &bitslice(*key));
+    }
+
+    inv_shift_rows(&mut q);
+    inv_sub_bytes(&mut q);
+    add_round_key(&mut q, &bitslice(keys[nr]));
+    unbitslice(&q)
+}
+
+#[test]
+fn bitslice_test() {
+    let x = Block::from(rand::random::<u128>());
+    assert_eq!(x, unbitslice(&bitslice(x)));
+
+    let q = bitslice(Block::from(0x80u128 << 8));
+    assert_eq!(q, [0, 0, 0, 0, 0, 0, 0, 1 << 1]);
+}
+
+#[test]
+fn sbox_test() {
+    use crate::sse2neon::AES_SBOX;
+
+    for i in 0..16 {
+        let mut bytes = [0u8; 16];
+        for (j, b) in bytes.iter_mut().enumerate() {
+            *b = (16 * i + j) as u8;
+        }
+        let mut q = bitslice(Block::from(u128::from_le_bytes(bytes)));
+        sub_bytes(&mut q);
+        let res = u128::from(unbitslice(&q)).to_le_bytes();
+        for (j, b) in res.iter().enumerate() {
+            assert_eq!(*b, AES_SBOX[16 * i + j]);
+        }
+    }
+}
+
+#[test]
+fn inv_sbox_test() {
+    use crate::sse2neon::AES_SBOX;
+
+    for i in 0..16 {
+        let mut bytes = [0u8; 16];
+        for (j, b) in bytes.iter_mut().enumerate() {
+            *b = AES_SBOX[16 * i + j];
+        }
+        let mut q = bitslice(Block::from(u128::from_le_bytes(bytes)));
+        inv_sub_bytes(&mut q);
+        let res = u128::from(unbitslice(&q)).to_le_bytes();
+        for (j, b) in res.iter().enumerate() {
+            assert_eq!(*b as usize, 16 * i + j);
+        }
+    }
+
+    let x = Block::from(rand::random::<u128>());
+    let mut q = bitslice(x);
+    shift_rows(&mut q);
+    mix_columns(&mut q);
+    inv_mix_columns(&mut q);
+    inv_shift_rows(&mut q);
+    assert_eq!(x, unbitslice(&q));
+}
+
+#[test]
+fn soft_aes_test() {
+    // FIPS-197, Appendix C.1.
+    let key = Block::from(u128::from_le_bytes([
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ]));
+    let ptxt = Block::from(u128::from_le_bytes([
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ]));
+    let ctxt = Block::from(u128::from_le_bytes([
+        0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5,
+        0x5a,
+    ]));
+    assert_eq!(encrypt(&expand_key(key), ptxt), ctxt);
+    assert_eq!(decrypt(&inverse_keys(&expand_key(key)), ctxt), ptxt);
+
+    let c = encrypt(&expand_key(Block::ZERO), Block::ZERO);
+    assert_eq!(c, Block::from(0x2e2b34ca59fa4c883b2c8aefd44be966));
+
+    // FIPS-197, Appendix C.3.
+    let key = [
+        key,
+        Block::from(u128::from_le_bytes([
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+            0x1e, 0x1f,
+        ])),
+    ];
+    let ctxt = Block::from(u128::from_le_bytes([
+        0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49, 0x60,
+        0x89,
+    ]));
+    assert_eq!(encrypt(&expand_key_256(key), ptxt), ctxt);
+    assert_eq!(decrypt(&inverse_keys(&expand_key_256(key)), ctxt), ptxt);
+}
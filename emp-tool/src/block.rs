@@ -19,6 +19,8 @@ use core::arch::x86_64::*;
 #[cfg(target_arch = "aarch64")]
 use crate::{_mm_and_si128, _mm_shuffle_epi32, _mm_xor_si128};
 
+use crate::cpu_features::{Backend, CpuFeatures};
+
 /// A 128-bit chunk type.\
 /// It is also viewed as an element in `GF(2^128)` with polynomial `x^128 + x^7 + x^2 + x + 1`\
 /// Use intrinsics whenever available to speedup.\
@@ -37,15 +39,25 @@ pub struct Block(pub uint8x16_t);
 #[repr(transparent)]
 pub struct Block(pub __m128i);
 
+/// A 128-bit chunk type.\
+/// It is also viewed as an element in `GF(2^128)` with polynomial `x^128 + x^7 + x^2 + x + 1`\
+/// This is the portable backend for targets other than aarch64 and x86/x86_64.
+/// As with `__m128i` on x86, the bytes in memory are the little-endian bytes of the value on every target,
+/// so use `u128::from` and `Block::from` instead of the inner field for arithmetic.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct Block(pub u128);
+
 unsafe impl Pod for Block {}
 unsafe impl Zeroable for Block {}
 
 impl Block {
     /// The constant block with value `0`.
-    pub const ZERO: Block = Block(unsafe { mem::transmute(0u128) });
+    pub const ZERO: Block = unsafe { mem::transmute::<u128, Block>(0u128) };
 
     /// The constant block with value `0xFFFF_FFFF_FFFF_FFFF`.
-    pub const ONES: Block = Block(unsafe { mem::transmute(u128::MAX) });
+    pub const ONES: Block = unsafe { mem::transmute::<u128, Block>(u128::MAX) };
 
     /// The select array with `ZERO_BLOCK` and `ONES_BLOCK`.
     pub const SELECT_MASK: [Block; 2] = [Block::ZERO, Block::ONES];
@@ -62,12 +74,22 @@ impl Block {
         unsafe {
             Self(_mm_loadu_si128(bytes.as_ptr() as *const __m128i))
         }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            Self::from(*bytes)
+        }
     }
 
     /// Compute the carry-less multiplication of two field elements.
     /// The output consists of two Blocks.
     #[inline(always)]
     pub fn clmul(self, x: &Self) -> (Self, Self) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if !CpuFeatures::get().pclmulqdq {
+            let (lo, hi) = clmul_soft(self.into(), (*x).into());
+            return (Block::from(lo), Block::from(hi));
+        }
         unsafe { self.clmul_unsafe(x) }
     }
 
@@ -102,22 +124,27 @@ impl Block {
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     #[target_feature(enable = "pclmulqdq")]
     unsafe fn clmul_unsafe(self, x: &Self) -> (Block, Block) {
-        unsafe {
-            let h = self.0;
-            let y = x.0;
-
-            let tmp3 = _mm_clmulepi64_si128(h, y, 0x00);
-            let tmp4 = _mm_clmulepi64_si128(h, y, 0x10);
-            let tmp5 = _mm_clmulepi64_si128(h, y, 0x01);
-            let tmp6 = _mm_clmulepi64_si128(h, y, 0x11);
-
-            let tmp4 = _mm_xor_si128(tmp4, tmp5);
-            let tmp5 = _mm_slli_si128(tmp4, 8);
-            let tmp4 = _mm_srli_si128(tmp4, 8);
-            let tmp3 = _mm_xor_si128(tmp3, tmp5);
-            let tmp6 = _mm_xor_si128(tmp6, tmp4);
-            (Block(tmp3), Block(tmp6))
-        }
+        let h = self.0;
+        let y = x.0;
+
+        let tmp3 = _mm_clmulepi64_si128(h, y, 0x00);
+        let tmp4 = _mm_clmulepi64_si128(h, y, 0x10);
+        let tmp5 = _mm_clmulepi64_si128(h, y, 0x01);
+        let tmp6 = _mm_clmulepi64_si128(h, y, 0x11);
+
+        let tmp4 = _mm_xor_si128(tmp4, tmp5);
+        let tmp5 = _mm_slli_si128(tmp4, 8);
+        let tmp4 = _mm_srli_si128(tmp4, 8);
+        let tmp3 = _mm_xor_si128(tmp3, tmp5);
+        let tmp6 = _mm_xor_si128(tmp6, tmp4);
+        (Block(tmp3), Block(tmp6))
+    }
+
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn clmul_unsafe(self, x: &Self) -> (Block, Block) {
+        let (lo, hi) = clmul_soft(u128::from(self), u128::from(*x));
+        (Block::from(lo), Block::from(hi))
     }
 
     /// The multiplication of two field elements.
@@ -192,6 +219,12 @@ impl Block {
         Block(veorq_u8(tmp3, tmp6))
     }
 
+    #[inline]
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe fn reduce_unsafe(x: &Block, y: &Block) -> Block {
+        Block::from(reduce_soft(u128::from(*x), u128::from(*y)))
+    }
+
     /// Get the least significant bit of the Block.
     #[inline(always)]
     pub fn get_lsb(&self) -> bool {
@@ -228,14 +261,86 @@ impl Block {
     #[inline(always)]
     pub fn inn_prdt_no_red(a: &[Block], b: &[Block]) -> (Block, Block) {
         assert_eq!(a.len(), b.len());
+        // The features are checked once here rather than in each multiplication.
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            let features = CpuFeatures::get();
+            if features.vpclmulqdq {
+                return unsafe { Block::inn_prdt_no_red_vpclmul(a, b) };
+            }
+            if !features.pclmulqdq {
+                return Block::inn_prdt_no_red_soft(a, b);
+            }
+        }
+        unsafe { Block::inn_prdt_no_red_unsafe(a, b) }
+    }
+
+    // Compute the inner product with the carry-less multiplication of the target.
+    #[inline]
+    #[cfg_attr(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature(enable = "pclmulqdq")
+    )]
+    #[cfg_attr(target_arch = "aarch64", target_feature(enable = "neon"))]
+    unsafe fn inn_prdt_no_red_unsafe(a: &[Block], b: &[Block]) -> (Block, Block) {
         a.iter()
             .zip(b.iter())
-            .fold((Block::default(), Block::default()), |acc, (x, y)| {
-                let t = x.clmul(y);
+            .fold((Block::ZERO, Block::ZERO), |acc, (x, y)| {
+                let t = x.clmul_unsafe(y);
                 (t.0 ^ acc.0, t.1 ^ acc.1)
             })
     }
 
+    // Compute the inner product without intrinsics.
+    #[inline]
+    fn inn_prdt_no_red_soft(a: &[Block], b: &[Block]) -> (Block, Block) {
+        let (lo, hi) = a.iter().zip(b.iter()).fold((0, 0), |acc, (x, y)| {
+            let t = clmul_soft(u128::from(*x), u128::from(*y));
+            (t.0 ^ acc.0, t.1 ^ acc.1)
+        });
+        (Block::from(lo), Block::from(hi))
+    }
+
+    // Compute the inner product with VPCLMULQDQ, 4 pairs of blocks per instruction.
+    #[inline]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "pclmulqdq,avx512f,vpclmulqdq")]
+    unsafe fn inn_prdt_no_red_vpclmul(a: &[Block], b: &[Block]) -> (Block, Block) {
+        let len = a.len() / 4 * 4;
+        let mut lo = _mm512_setzero_si512();
+        let mut mid = _mm512_setzero_si512();
+        let mut hi = _mm512_setzero_si512();
+
+        for (x, y) in a[..len].chunks_exact(4).zip(b[..len].chunks_exact(4)) {
+            let x = _mm512_loadu_si512(x.as_ptr() as *const _);
+            let y = _mm512_loadu_si512(y.as_ptr() as *const _);
+            lo = _mm512_xor_si512(lo, _mm512_clmulepi64_epi128(x, y, 0x00));
+            mid = _mm512_xor_si512(mid, _mm512_clmulepi64_epi128(x, y, 0x10));
+            mid = _mm512_xor_si512(mid, _mm512_clmulepi64_epi128(x, y, 0x01));
+            hi = _mm512_xor_si512(hi, _mm512_clmulepi64_epi128(x, y, 0x11));
+        }
+
+        // Sum up the 4 lanes.
+        let mut buf = [Block::ZERO; 12];
+        _mm512_storeu_si512(buf.as_mut_ptr() as *mut _, lo);
+        _mm512_storeu_si512(buf[4..].as_mut_ptr() as *mut _, mid);
+        _mm512_storeu_si512(buf[8..].as_mut_ptr() as *mut _, hi);
+        let lo = buf[0] ^ buf[1] ^ buf[2] ^ buf[3];
+        let mid = buf[4] ^ buf[5] ^ buf[6] ^ buf[7];
+        let hi = buf[8] ^ buf[9] ^ buf[10] ^ buf[11];
+
+        let mut res = (
+            Block(_mm_xor_si128(lo.0, _mm_slli_si128(mid.0, 8))),
+            Block(_mm_xor_si128(hi.0, _mm_srli_si128(mid.0, 8))),
+        );
+        for (x, y) in a[len..].iter().zip(b[len..].iter()) {
+            let t = x.clmul_unsafe(y);
+            res.0 ^= t.0;
+            res.1 ^= t.1;
+        }
+        res
+    }
+
     /// Compute the inner product of two block vectors.
     #[inline(always)]
     pub fn inn_prdt_red(a: &[Block], b: &[Block]) -> Block {
@@ -243,6 +348,28 @@ impl Block {
         Block::reduce(&x, &y)
     }
 
+    /// Compute the inner product without reducing the polynomial with a given backend, which panics if the backend is not supported.
+    #[doc(hidden)]
+    pub fn inn_prdt_no_red_with(a: &[Block], b: &[Block], backend: Backend) -> (Block, Block) {
+        assert_eq!(a.len(), b.len());
+        assert!(CpuFeatures::get().supports_clmul(backend));
+        match backend {
+            Backend::Soft => Block::inn_prdt_no_red_soft(a, b),
+            Backend::Simd => unsafe { Block::inn_prdt_no_red_unsafe(a, b) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Wide => unsafe { Block::inn_prdt_no_red_vpclmul(a, b) },
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::Wide => unreachable!(),
+        }
+    }
+
+    /// Compute the inner product with a given backend, which panics if the backend is not supported.
+    #[doc(hidden)]
+    pub fn inn_prdt_red_with(a: &[Block], b: &[Block], backend: Backend) -> Block {
+        let (x, y) = Block::inn_prdt_no_red_with(a, b, backend);
+        Block::reduce(&x, &y)
+    }
+
     /// Compute the exponential function of the block.
     #[inline(always)]
     pub fn pow(&self, exp: u128) -> Self {
@@ -286,7 +413,7 @@ impl Block {
             let x = a.0;
             Block(_mm_xor_si128(
                 _mm_shuffle_epi32(x, 78),
-                _mm_and_si128(x, mem::transmute([0u64, u64::MAX])),
+                _mm_and_si128(x, mem::transmute::<[u64; 2], __m128i>([0u64, u64::MAX])),
             ))
         }
 
@@ -295,10 +422,53 @@ impl Block {
             let x = a.0;
             Block(_mm_xor_si128!(
                 _mm_shuffle_epi32!(x, 78),
-                _mm_and_si128!(x, mem::transmute([0u64, u64::MAX]))
+                _mm_and_si128!(x, mem::transmute::<[u64; 2], uint8x16_t>([0u64, u64::MAX]))
             ))
         }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            let x = u128::from(a);
+            Block::from(x.rotate_left(64) ^ (x & (u128::MAX << 64)))
+        }
+    }
+}
+
+// Compute the carry-less multiplication of two 64-bit integers in constant time.
+#[inline(always)]
+fn clmul64_soft(x: u64, y: u64) -> u128 {
+    let x = x as u128;
+    let mut res = 0u128;
+    for i in 0..64 {
+        let mask = 0u128.wrapping_sub(((y >> i) & 1) as u128);
+        res ^= (x << i) & mask;
     }
+    res
+}
+
+// Compute the carry-less multiplication of two 128-bit integers without intrinsics.
+// The output is the lower and higher 128 bits of the product.
+#[cfg_attr(target_arch = "aarch64", allow(dead_code))]
+#[inline(always)]
+fn clmul_soft(x: u128, y: u128) -> (u128, u128) {
+    let (x0, x1) = (x as u64, (x >> 64) as u64);
+    let (y0, y1) = (y as u64, (y >> 64) as u64);
+
+    let lo = clmul64_soft(x0, y0);
+    let mid = clmul64_soft(x1, y0) ^ clmul64_soft(x0, y1);
+    let hi = clmul64_soft(x1, y1);
+    (lo ^ (mid << 64), hi ^ (mid >> 64))
+}
+
+// Reduce the polynomial `x + y * X^128` with `X^128 + X^7 + X^2 + X + 1` without intrinsics.
+#[cfg_attr(
+    any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"),
+    allow(dead_code)
+)]
+#[inline(always)]
+fn reduce_soft(x: u128, y: u128) -> u128 {
+    let y = y ^ (y >> 127) ^ (y >> 126) ^ (y >> 121);
+    x ^ y ^ (y << 1) ^ (y << 2) ^ (y << 7)
 }
 
 impl Default for Block {
@@ -308,6 +478,20 @@ impl Default for Block {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Block {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        <[u8; 16]>::from(*self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Block {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <[u8; 16]>::deserialize(deserializer).map(Block::from)
+    }
+}
+
 impl From<Block> for [u8; 16] {
     #[inline(always)]
     fn from(m: Block) -> [u8; 16] {
@@ -318,14 +502,15 @@ impl From<Block> for [u8; 16] {
 impl From<Block> for [u64; 2] {
     #[inline(always)]
     fn from(m: Block) -> Self {
-        bytemuck::cast(m)
+        let x = u128::from(m);
+        [x as u64, (x >> 64) as u64]
     }
 }
 
 impl From<Block> for u128 {
     #[inline(always)]
     fn from(m: Block) -> u128 {
-        bytemuck::cast(m)
+        u128::from_le_bytes(bytemuck::cast(m))
     }
 }
 
@@ -339,13 +524,13 @@ impl From<[u8; 16]> for Block {
 impl From<[u64; 2]> for Block {
     #[inline(always)]
     fn from(m: [u64; 2]) -> Self {
-        bytemuck::cast(m)
+        Block::from(m[0] as u128 | (m[1] as u128) << 64)
     }
 }
 impl From<u128> for Block {
     #[inline(always)]
     fn from(m: u128) -> Block {
-        bytemuck::cast(m)
+        bytemuck::cast(m.to_le_bytes())
     }
 }
 
@@ -405,6 +590,11 @@ impl BitXor for Block {
         unsafe {
             Self(_mm_xor_si128(self.0, other.0))
         }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            Self(self.0 ^ other.0)
+        }
     }
 }
 
@@ -428,6 +618,11 @@ impl BitOr for Block {
         unsafe {
             Self(_mm_or_si128(self.0, rhs.0))
         }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            Self(self.0 | rhs.0)
+        }
     }
 }
 
@@ -451,6 +646,11 @@ impl BitAnd for Block {
         unsafe {
             Self(_mm_and_si128(self.0, rhs.0))
         }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            Self(self.0 & rhs.0)
+        }
     }
 }
 
@@ -506,6 +706,25 @@ fn type_test() {
 
     let y = blk.as_ref();
     assert_eq!(blk, Block::try_from_slice(y).unwrap());
+
+    // The byte layout is the same as x86 on every target.
+    let x: u128 = 0x0f0e0d0c0b0a09080706050403020100;
+    let bytes: [u8; 16] = std::array::from_fn(|i| i as u8);
+    assert_eq!(<[u8; 16]>::from(Block::from(x)), bytes);
+    assert_eq!(Block::from(x).as_ref(), bytes);
+    assert_eq!(Block::from(bytes), Block::from(x));
+    assert_eq!(
+        Block::from([0x0706050403020100, 0x0f0e0d0c0b0a0908]),
+        Block::from(x)
+    );
+    assert_eq!(
+        <[u64; 2]>::from(Block::from(x)),
+        [0x0706050403020100, 0x0f0e0d0c0b0a0908]
+    );
+    assert_eq!(
+        Block::sigma(Block::from(x)),
+        Block::from([0x0f0e0d0c0b0a0908, 0x0808080808080808])
+    );
 }
 
 #[test]
@@ -519,6 +738,26 @@ fn clmul_test() {
     assert_eq!(x.clmul(&y), (res1, res2));
 }
 
+#[test]
+fn clmul_soft_test() {
+    let x: u128 = 0x7b5b54657374566563746f725d53475d;
+    let y: u128 = 0x48692853686179295b477565726f6e5d;
+    let res1: u128 = 0xd857e24982ab861c929633d5d36f0451;
+    let res2: u128 = 0x1d1e1f2c592e7c45d7946a682e55e763;
+    let z: u128 = 0x040229a09a5ed12e7e4e10da323506d2;
+    assert_eq!(clmul_soft(x, y), (res1, res2));
+    assert_eq!(reduce_soft(res1, res2), z);
+
+    let x = Block::from(rand::random::<u128>());
+    let y = Block::from(rand::random::<u128>());
+    let (lo, hi) = x.clmul(&y);
+    assert_eq!(clmul_soft(x.into(), y.into()), (lo.into(), hi.into()));
+    assert_eq!(
+        reduce_soft(lo.into(), hi.into()),
+        Block::reduce(&lo, &hi).into()
+    );
+}
+
 #[test]
 fn reduce_test() {
     let x = Block::from(0xd857e24982ab861c929633d5d36f0451);
@@ -582,7 +821,7 @@ fn lsb_test() {
     assert_eq!((x & 1) == 1, y.get_lsb());
 
     y.set_lsb();
-    assert_eq!(y.get_lsb(), true);
+    assert!(y.get_lsb());
 }
 
 #[test]
@@ -603,8 +842,8 @@ fn inn_prdt_test() {
         b.push(Block::from(r));
 
         let z = a[i].clmul(&b[i]);
-        c.0 = c.0 ^ z.0;
-        c.1 = c.1 ^ z.1;
+        c.0 ^= z.0;
+        c.1 ^= z.1;
 
         let x = a[i] * b[i];
         d ^= x;
@@ -614,6 +853,37 @@ fn inn_prdt_test() {
     assert_eq!(d, Block::inn_prdt_red(&a, &b));
 }
 
+#[test]
+fn inn_prdt_tail_test() {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
+    let mut rng = ChaCha12Rng::from_entropy();
+
+    for size in [1, 3, 4, 7, 17, 35] {
+        let a: Vec<Block> = (0..size).map(|_| rng.gen()).collect();
+        let b: Vec<Block> = (0..size).map(|_| rng.gen()).collect();
+        let c = a
+            .iter()
+            .zip(b.iter())
+            .fold((Block::ZERO, Block::ZERO), |acc, (x, y)| {
+                let t = x.clmul(y);
+                (acc.0 ^ t.0, acc.1 ^ t.1)
+            });
+        assert_eq!(c, Block::inn_prdt_no_red(&a, &b));
+
+        let features = CpuFeatures::get();
+        for backend in [Backend::Soft, Backend::Simd, Backend::Wide] {
+            if features.supports_clmul(backend) {
+                assert_eq!(Block::inn_prdt_no_red_with(&a, &b, backend), c);
+                assert_eq!(
+                    Block::inn_prdt_red_with(&a, &b, backend),
+                    Block::reduce(&c.0, &c.1)
+                );
+            }
+        }
+    }
+}
+
 #[test]
 fn pow_inverse_test() {
     let one = Block::from(1u128);
@@ -622,7 +892,7 @@ fn pow_inverse_test() {
     let mut pow = one;
 
     for _ in 0..exp {
-        pow = pow * x;
+        pow *= x;
     }
     assert_eq!(pow, x.pow(exp));
     assert_eq!(one, x * x.inverse());
@@ -0,0 +1,140 @@
+//! Implement coin-tossing protocols based on commit-then-reveal.
+
+use crate::{
+    commit::{recv_commit, recv_reveal, reveal, send_commit},
+    constants::{ALICE, BOB},
+    io_channel::IOChannel,
+    prg::Prg,
+    Block,
+};
+use rand_core::SeedableRng;
+use std::io::Result;
+
+/// Toss a random block jointly by `ALICE` and `BOB`, where `party` is the role of the caller.\
+/// `ALICE` commits to her share, `BOB` sends his share in the clear, and then `ALICE` opens her share.
+/// The output is the XOR of the two shares.
+/// `BOB` gets an error of kind `InvalidData` if `ALICE` fails to open her commitment.
+pub fn coin_toss<IO: IOChannel>(io: &mut IO, party: usize, prg: &mut Prg) -> Result<Block> {
+    assert!(party == ALICE || party == BOB);
+    let share = prg.random_block();
+    if party == ALICE {
+        let decom = send_commit(io, share.as_ref(), prg)?;
+        io.flush()?;
+        let other = io.recv_block()?;
+        reveal(io, share.as_ref(), &decom)?;
+        io.flush()?;
+        Ok(share ^ other)
+    } else {
+        let com = recv_commit(io)?;
+        io.send_block(&share)?;
+        io.flush()?;
+        let mut other = Block::ZERO;
+        recv_reveal(io, &com, other.as_mut())?;
+        Ok(share ^ other)
+    }
+}
+
+/// Toss a random seed jointly by `ALICE` and `BOB`, and new a `Prg` with it.
+#[inline(always)]
+pub fn coin_toss_prg<IO: IOChannel>(io: &mut IO, party: usize, prg: &mut Prg) -> Result<Prg> {
+    coin_toss(io, party, prg).map(Prg::from_seed)
+}
+
+/// Toss a random block jointly by `n = ios.len()` parties, where `party` in `[0, n)` is the index of the caller.\
+/// `ios[i]` is the channel to party `i`, and `ios[party]` must be `None`.
+/// Every party first commits to its share to all others, and opens it after receiving all commitments.
+/// The output is the XOR of all shares, which is random as long as one party is honest.
+/// An error of kind `InvalidData` is returned if any party fails to open its commitment.
+pub fn multi_party_coin_toss<IO: IOChannel>(
+    ios: &mut [Option<IO>],
+    party: usize,
+    prg: &mut Prg,
+) -> Result<Block> {
+    assert!(party < ios.len() && ios[party].is_none());
+    let share = prg.random_block();
+
+    let mut decoms = Vec::with_capacity(ios.len());
+    for io in ios.iter_mut().flatten() {
+        decoms.push(send_commit(io, share.as_ref(), prg)?);
+        io.flush()?;
+    }
+    let mut coms = Vec::with_capacity(ios.len());
+    for io in ios.iter_mut().flatten() {
+        coms.push(recv_commit(io)?);
+    }
+
+    for (io, decom) in ios.iter_mut().flatten().zip(&decoms) {
+        reveal(io, share.as_ref(), decom)?;
+        io.flush()?;
+    }
+    let mut res = share;
+    for (io, com) in ios.iter_mut().flatten().zip(&coms) {
+        let mut other = Block::ZERO;
+        recv_reveal(io, com, other.as_mut())?;
+        res ^= other;
+    }
+    Ok(res)
+}
+
+/// Toss a random seed jointly by `n = ios.len()` parties, and new a `Prg` with it.
+#[inline(always)]
+pub fn multi_party_coin_toss_prg<IO: IOChannel>(
+    ios: &mut [Option<IO>],
+    party: usize,
+    prg: &mut Prg,
+) -> Result<Prg> {
+    multi_party_coin_toss(ios, party, prg).map(Prg::from_seed)
+}
+
+#[test]
+fn coin_toss_test() {
+    use crate::io_channel::LocalIO;
+
+    let (mut io0, mut io1) = LocalIO::new_pair();
+    let handle = std::thread::spawn(move || {
+        let mut prg = Prg::new();
+        let x = coin_toss(&mut io0, ALICE, &mut prg).unwrap();
+        let r = coin_toss_prg(&mut io0, ALICE, &mut prg)
+            .unwrap()
+            .random_block();
+        (x, r)
+    });
+    let mut prg = Prg::new();
+    let x = coin_toss(&mut io1, BOB, &mut prg).unwrap();
+    let r = coin_toss_prg(&mut io1, BOB, &mut prg)
+        .unwrap()
+        .random_block();
+    assert_eq!(handle.join().unwrap(), (x, r));
+    assert_ne!(x, Block::ZERO);
+}
+
+#[test]
+#[allow(clippy::needless_range_loop)]
+fn multi_party_coin_toss_test() {
+    use crate::io_channel::LocalIO;
+
+    const N: usize = 4;
+    let mut ios: Vec<Vec<Option<LocalIO>>> =
+        (0..N).map(|_| (0..N).map(|_| None).collect()).collect();
+    for i in 0..N {
+        for j in i + 1..N {
+            let (io_ij, io_ji) = LocalIO::new_pair();
+            ios[i][j] = Some(io_ij);
+            ios[j][i] = Some(io_ji);
+        }
+    }
+
+    let handles: Vec<_> = ios
+        .into_iter()
+        .enumerate()
+        .map(|(party, mut ios)| {
+            std::thread::spawn(move || {
+                let mut prg = Prg::new();
+                multi_party_coin_toss(&mut ios, party, &mut prg).unwrap()
+            })
+        })
+        .collect();
+    let res: Vec<Block> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert!(res.iter().all(|&x| x == res[0]));
+    assert_ne!(res[0], Block::ZERO);
+}
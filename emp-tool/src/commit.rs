@@ -0,0 +1,164 @@
+//! Implement a hash-based commitment scheme and its interactive commit/reveal helpers.
+
+use crate::{hash::Hash, io_channel::IOChannel, prg::Prg, Block};
+use std::io::{Error, ErrorKind, Result};
+
+/// The prefix absorbed at the beginning of every commitment.
+const DOMAIN_PREFIX: &[u8] = b"emp-rust/commit/v1";
+
+/// A commitment `H(r, m)` to a message `m` with randomness `r`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Commitment(pub [u8; Hash::DIGEST_SIZE]);
+
+/// The randomness `r` to open a commitment.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Decommitment(pub Block);
+
+/// Compute the commitment to `msg` with the randomness `decom`.
+#[inline(always)]
+fn compute(msg: &[u8], decom: &Decommitment) -> Commitment {
+    let mut hasher = Hash::new();
+    hasher.update(DOMAIN_PREFIX);
+    hasher.update_block(decom.0);
+    hasher.update(msg);
+    Commitment(hasher.finalize())
+}
+
+/// Commit to `msg` with randomness sampled from `prg`.
+#[inline(always)]
+pub fn commit(msg: &[u8], prg: &mut Prg) -> (Commitment, Decommitment) {
+    let decom = Decommitment(prg.random_block());
+    (compute(msg, &decom), decom)
+}
+
+/// Verify that `com` is opened to `msg` by `decom`.
+#[inline(always)]
+pub fn verify(com: &Commitment, msg: &[u8], decom: &Decommitment) -> bool {
+    compute(msg, decom) == *com
+}
+
+/// Commit to a block slice as a single message.
+#[inline(always)]
+pub fn commit_block_slice(msg: &[Block], prg: &mut Prg) -> (Commitment, Decommitment) {
+    commit(bytemuck::cast_slice(msg), prg)
+}
+
+/// Verify that `com` is opened to the block slice `msg` by `decom`.
+#[inline(always)]
+pub fn verify_block_slice(com: &Commitment, msg: &[Block], decom: &Decommitment) -> bool {
+    verify(com, bytemuck::cast_slice(msg), decom)
+}
+
+/// Commit to each block of `msgs` independently, such that each commitment can be opened on its own.
+pub fn batch_commit_blocks(msgs: &[Block], prg: &mut Prg) -> (Vec<Commitment>, Vec<Decommitment>) {
+    let mut decoms = vec![Block::ZERO; msgs.len()];
+    prg.random_blocks(&mut decoms);
+    msgs.iter()
+        .zip(decoms)
+        .map(|(m, r)| {
+            let decom = Decommitment(r);
+            (compute(m.as_ref(), &decom), decom)
+        })
+        .unzip()
+}
+
+/// Verify the commitments to each block of `msgs` produced by `batch_commit_blocks`.
+pub fn batch_verify_blocks(coms: &[Commitment], msgs: &[Block], decoms: &[Decommitment]) -> bool {
+    assert_eq!(coms.len(), msgs.len());
+    assert_eq!(coms.len(), decoms.len());
+    coms.iter()
+        .zip(msgs.iter().zip(decoms))
+        .all(|(com, (m, decom))| verify(com, m.as_ref(), decom))
+}
+
+/// Commit to `msg` and send the commitment through `io`.\
+/// Return the decommitment that is later sent by `reveal`.
+#[inline(always)]
+pub fn send_commit<IO: IOChannel>(io: &mut IO, msg: &[u8], prg: &mut Prg) -> Result<Decommitment> {
+    let (com, decom) = commit(msg, prg);
+    io.send_bytes(&com.0)?;
+    Ok(decom)
+}
+
+/// Receive a commitment from `io`.
+#[inline(always)]
+pub fn recv_commit<IO: IOChannel>(io: &mut IO) -> Result<Commitment> {
+    let mut com = Commitment([0u8; Hash::DIGEST_SIZE]);
+    io.recv_bytes(&mut com.0)?;
+    Ok(com)
+}
+
+/// Send the message `msg` and the decommitment `decom` through `io`.
+#[inline(always)]
+pub fn reveal<IO: IOChannel>(io: &mut IO, msg: &[u8], decom: &Decommitment) -> Result<()> {
+    io.send_block(&decom.0)?;
+    io.send_bytes(msg)
+}
+
+/// Receive the message into `msg` and the decommitment from `io`, and verify them against `com`.\
+/// Return an error of kind `InvalidData` if the verification fails.
+#[inline(always)]
+pub fn recv_reveal<IO: IOChannel>(io: &mut IO, com: &Commitment, msg: &mut [u8]) -> Result<()> {
+    let decom = Decommitment(io.recv_block()?);
+    io.recv_bytes(msg)?;
+    if verify(com, msg, &decom) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "Failed to open the commitment",
+        ))
+    }
+}
+
+#[test]
+fn commit_test() {
+    let mut prg = Prg::new();
+    let msg = b"message";
+    let (com, decom) = commit(msg, &mut prg);
+    assert!(verify(&com, msg, &decom));
+    assert!(!verify(&com, b"massage", &decom));
+    assert!(!verify(&com, msg, &Decommitment(prg.random_block())));
+    // The commitment is randomized.
+    assert_ne!(commit(msg, &mut prg).0, com);
+
+    let mut blks = vec![Block::ZERO; 10];
+    prg.random_blocks(&mut blks);
+    let (com, decom) = commit_block_slice(&blks, &mut prg);
+    assert!(verify_block_slice(&com, &blks, &decom));
+    assert!(!verify_block_slice(&com, &blks[1..], &decom));
+
+    let (coms, decoms) = batch_commit_blocks(&blks, &mut prg);
+    assert!(batch_verify_blocks(&coms, &blks, &decoms));
+    assert!(verify(&coms[3], blks[3].as_ref(), &decoms[3]));
+    blks.swap(0, 1);
+    assert!(!batch_verify_blocks(&coms, &blks, &decoms));
+}
+
+#[test]
+fn commit_io_test() {
+    use crate::io_channel::NetIO;
+
+    let addr = "127.0.0.1:12347";
+    let msg = rand::random::<[u8; 20]>();
+
+    let handle = std::thread::spawn(move || {
+        let mut io = NetIO::new(true, addr).unwrap();
+        let mut prg = Prg::new();
+        let decom = send_commit(&mut io, &msg, &mut prg).unwrap();
+        reveal(&mut io, &msg, &decom).unwrap();
+        // Cheat by revealing another message.
+        let decom = send_commit(&mut io, &msg, &mut prg).unwrap();
+        reveal(&mut io, &[0u8; 20], &decom).unwrap();
+    });
+
+    let mut io = NetIO::new(false, addr).unwrap();
+    let mut recv_msg = [0u8; 20];
+    let com = recv_commit(&mut io).unwrap();
+    recv_reveal(&mut io, &com, &mut recv_msg).unwrap();
+    assert_eq!(recv_msg, msg);
+    let com = recv_commit(&mut io).unwrap();
+    let err = recv_reveal(&mut io, &com, &mut recv_msg).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    handle.join().unwrap();
+}
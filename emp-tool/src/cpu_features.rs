@@ -0,0 +1,143 @@
+//! Detect CPU features at runtime to select intrinsic or software backends.
+
+use std::sync::OnceLock;
+
+/// The CPU features used by the backends of `Aes` and `Block`.\
+/// On x86/x86_64, the features are detected at runtime and cached after the first query.\
+/// On aarch64, the AES extension is assumed to be available.\
+/// On other targets, the portable software backend is always used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CpuFeatures {
+    /// AES instructions, i.e., AES-NI on x86/x86_64.
+    pub aes: bool,
+
+    /// Carry-less multiplication, i.e., PCLMULQDQ on x86/x86_64.
+    pub pclmulqdq: bool,
+
+    /// AES instructions on 512-bit vectors, i.e., VAES with AVX-512F on x86/x86_64.
+    pub vaes: bool,
+
+    /// Carry-less multiplication on 512-bit vectors, i.e., VPCLMULQDQ with AVX-512F on x86/x86_64.
+    pub vpclmulqdq: bool,
+}
+
+impl CpuFeatures {
+    /// Get the features of the running CPU.
+    #[inline(always)]
+    pub fn get() -> Self {
+        static FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+        *FEATURES.get_or_init(CpuFeatures::detect)
+    }
+
+    /// Get the fastest backend of AES.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn aes_backend(&self) -> Backend {
+        Backend::select(self.aes, self.vaes)
+    }
+
+    /// Get the fastest backend of carry-less multiplication.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn clmul_backend(&self) -> Backend {
+        Backend::select(self.pclmulqdq, self.vpclmulqdq)
+    }
+
+    /// Check whether the backend of AES is supported.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn supports_aes(&self, backend: Backend) -> bool {
+        backend.supported(self.aes, self.vaes)
+    }
+
+    /// Check whether the backend of carry-less multiplication is supported.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn supports_clmul(&self, backend: Backend) -> bool {
+        backend.supported(self.pclmulqdq, self.vpclmulqdq)
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn detect() -> Self {
+        let aes = is_x86_feature_detected!("aes") && is_x86_feature_detected!("sse2");
+        let pclmulqdq = is_x86_feature_detected!("pclmulqdq") && is_x86_feature_detected!("sse2");
+        let avx512f = is_x86_feature_detected!("avx512f");
+        Self {
+            aes,
+            pclmulqdq,
+            vaes: aes && avx512f && is_x86_feature_detected!("vaes"),
+            vpclmulqdq: pclmulqdq && avx512f && is_x86_feature_detected!("vpclmulqdq"),
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn detect() -> Self {
+        Self {
+            aes: true,
+            pclmulqdq: true,
+            vaes: false,
+            vpclmulqdq: false,
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    fn detect() -> Self {
+        Self {
+            aes: false,
+            pclmulqdq: false,
+            vaes: false,
+            vpclmulqdq: false,
+        }
+    }
+}
+
+/// A backend of the batch operations of `Aes` and `Block`.\
+/// It is only exposed for benchmarks and tests that run the backends side by side on the same inputs.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// The portable software backend.
+    Soft,
+
+    /// The 128-bit instructions, i.e., AES-NI and PCLMULQDQ on x86/x86_64, or the AES extension on aarch64.
+    Simd,
+
+    /// The 512-bit instructions, i.e., VAES and VPCLMULQDQ on x86/x86_64.
+    Wide,
+}
+
+impl Backend {
+    #[inline(always)]
+    fn select(simd: bool, wide: bool) -> Self {
+        if wide {
+            Backend::Wide
+        } else if simd {
+            Backend::Simd
+        } else {
+            Backend::Soft
+        }
+    }
+
+    #[inline(always)]
+    fn supported(self, simd: bool, wide: bool) -> bool {
+        match self {
+            Backend::Soft => true,
+            Backend::Simd => simd,
+            Backend::Wide => wide,
+        }
+    }
+}
+
+#[test]
+fn cpu_features_test() {
+    let features = CpuFeatures::get();
+    assert_eq!(features, CpuFeatures::detect());
+    assert_eq!(features, CpuFeatures::get());
+    assert!(features.supports_aes(features.aes_backend()));
+    assert!(features.supports_clmul(features.clmul_backend()));
+    assert_eq!(features.aes_backend() == Backend::Wide, features.vaes);
+    assert_eq!(
+        features.clmul_backend() == Backend::Soft,
+        !features.pclmulqdq
+    );
+}
@@ -0,0 +1,198 @@
+//! Implement the two-party distributed comparison function (DCF) of Boyle et al. (<https://eprint.iacr.org/2020/1392>, Figure 3) over the XOR group of `Block`.\
+//! The seeds are expanded with `TwoKeyPrp` as in `Dpf`, and the values of the children are expanded with another `TwoKeyPrp`.
+
+use crate::{aes::Aes, prg::Prg, tkprp::TwoKeyPrp, Block};
+use rayon::prelude::*;
+
+/// The maximum bit length of the domain of a DCF.
+pub const MAX_DCF_BITS: usize = 64;
+
+/// The key of a party in a DCF.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DcfKey {
+    /// The root seed, whose least significant bit is the control bit of the party.
+    pub seed: Block,
+
+    /// The correction words of the seeds in each level, whose least significant bits are zero.
+    pub cw_seeds: Vec<Block>,
+
+    /// The correction words of the values in each level.
+    pub cw_values: Vec<Block>,
+
+    /// The correction words of the control bits of the left and the right children in each level.
+    pub cw_bits: Vec<[bool; 2]>,
+
+    /// The correction word of the output.
+    pub cw_out: Block,
+}
+
+/// Struct of DCF with the domain `[0, 2^bits)` and outputs in `Block`.\
+/// The comparison function `f_{alpha, beta}` outputs `beta` on `x < alpha` and `Block::ZERO` elsewhere,
+/// and the outputs of the two keys on every point are XOR shares of it.
+pub struct Dcf {
+    seed_prp: TwoKeyPrp,
+    value_prp: TwoKeyPrp,
+    aes: Aes,
+    bits: usize,
+}
+
+impl Dcf {
+    /// New a DCF with the domain `[0, 2^bits)`, where `bits <= MAX_DCF_BITS`.
+    #[inline(always)]
+    pub fn new(bits: usize) -> Self {
+        assert!(bits <= MAX_DCF_BITS);
+        Self {
+            seed_prp: TwoKeyPrp::new([Block::ZERO, Block::from(1u128)]),
+            value_prp: TwoKeyPrp::new([Block::from(2u128), Block::from(3u128)]),
+            aes: Aes::new(Block::from(4u128)),
+            bits,
+        }
+    }
+
+    /// Get the bit length of the domain.
+    #[inline(always)]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    // Check that `x` is in the domain.
+    #[inline(always)]
+    fn check(&self, x: u64) {
+        assert_eq!(x.checked_shr(self.bits as u32).unwrap_or(0), 0);
+    }
+
+    // The `h`-th bit of `x` from the most significant one.
+    #[inline(always)]
+    fn bit(&self, x: u64, h: usize) -> usize {
+        ((x >> (self.bits - 1 - h)) & 1) as usize
+    }
+
+    // Convert a leaf into an output.
+    #[inline(always)]
+    fn convert(&self, s: Block) -> Block {
+        self.aes.encrypt_block(s) ^ s
+    }
+
+    /// Generate the keys of the two parties for the comparison function `f_{alpha, beta}`.
+    pub fn gen(&self, alpha: u64, beta: Block, prg: &mut Prg) -> (DcfKey, DcfKey) {
+        self.check(alpha);
+        let mut s = [prg.random_block(), prg.random_block()];
+        s[0] &= Block::from(!1u128);
+        s[1].set_lsb();
+        let seeds = s;
+
+        let mut cw_seeds = Vec::with_capacity(self.bits);
+        let mut cw_values = Vec::with_capacity(self.bits);
+        let mut cw_bits = Vec::with_capacity(self.bits);
+        // The XOR of the values of the two parties on the path to `alpha`.
+        let mut v_alpha = Block::ZERO;
+        let mut buf = [Block::ZERO; 4];
+        let mut v = [Block::ZERO; 4];
+        for h in 0..self.bits {
+            let a = self.bit(alpha, h);
+            // `buf` is `[s0_L, s0_R, s1_L, s1_R]`, and so is `v` for the values.
+            self.seed_prp.expand_2to4(&mut buf, &s);
+            self.value_prp.expand_2to4(&mut v, &s);
+
+            let cw = (buf[a ^ 1] ^ buf[2 + (a ^ 1)]) & Block::from(!1u128);
+            // All the points in the left subtree are less than `alpha` if the path goes right.
+            let mut cw_value = v_alpha ^ v[a ^ 1] ^ v[2 + (a ^ 1)];
+            if a == 1 {
+                cw_value ^= beta;
+            }
+            v_alpha ^= v[a] ^ v[2 + a] ^ cw_value;
+            let t = [
+                buf[0].get_lsb() ^ buf[2].get_lsb() ^ (a == 0),
+                buf[1].get_lsb() ^ buf[3].get_lsb() ^ (a == 1),
+            ];
+            let cw_keep = cw | Block::from(t[a] as u128);
+            for (b, s) in s.iter_mut().enumerate() {
+                *s = buf[2 * b + a] ^ (cw_keep & Block::SELECT_MASK[s.get_lsb() as usize]);
+            }
+            cw_seeds.push(cw);
+            cw_values.push(cw_value);
+            cw_bits.push(t);
+        }
+        let cw_out = v_alpha ^ self.convert(s[0]) ^ self.convert(s[1]);
+
+        let key = |seed| DcfKey {
+            seed,
+            cw_seeds: cw_seeds.clone(),
+            cw_values: cw_values.clone(),
+            cw_bits: cw_bits.clone(),
+            cw_out,
+        };
+        (key(seeds[0]), key(seeds[1]))
+    }
+
+    /// Evaluate the share of `f_{alpha, beta}(x)` with a key.
+    pub fn eval(&self, key: &DcfKey, x: u64) -> Block {
+        self.check(x);
+        assert_eq!(key.cw_seeds.len(), self.bits);
+        let mut s = key.seed;
+        let mut res = Block::ZERO;
+        for h in 0..self.bits {
+            let b = self.bit(x, h);
+            let mask = Block::SELECT_MASK[s.get_lsb() as usize];
+            let cw = key.cw_seeds[h] | Block::from(key.cw_bits[h][b] as u128);
+            res ^= self.value_prp.expand_child(s, b) ^ (key.cw_values[h] & mask);
+            s = self.seed_prp.expand_child(s, b) ^ (cw & mask);
+        }
+        res ^ self.convert(s) ^ (key.cw_out & Block::SELECT_MASK[s.get_lsb() as usize])
+    }
+
+    /// Evaluate the shares of `f_{alpha, beta}` on many points with a key in parallel.\
+    /// Output: `out`: `out[i]` is the share of `f_{alpha, beta}(xs[i])`, with size `xs.len()`.
+    pub fn eval_batch(&self, key: &DcfKey, xs: &[u64], out: &mut [Block]) {
+        assert_eq!(xs.len(), out.len());
+        out.par_iter_mut()
+            .zip(xs.par_iter())
+            .for_each(|(y, x)| *y = self.eval(key, *x));
+    }
+}
+
+#[test]
+fn dcf_test() {
+    use crate::{io_channel::LocalIO, IOChannel};
+
+    let mut prg = Prg::new();
+    for bits in [0, 1, 2, 7] {
+        let dcf = Dcf::new(bits);
+        for alpha in 0..1u64 << bits {
+            let beta = prg.random_block();
+            let (k0, k1) = dcf.gen(alpha, beta, &mut prg);
+            for x in 0..1u64 << bits {
+                let y = dcf.eval(&k0, x) ^ dcf.eval(&k1, x);
+                assert_eq!(y, if x < alpha { beta } else { Block::ZERO });
+            }
+        }
+    }
+
+    // The dealer sends the keys to two parties, who evaluate many points and reconstruct the outputs.
+    let bits = MAX_DCF_BITS;
+    let dcf = Dcf::new(bits);
+    let alpha = prg.random_below(u64::MAX - 1) + 1;
+    let beta = prg.random_block();
+    let (k0, k1) = dcf.gen(alpha, beta, &mut prg);
+    let mut xs: Vec<u64> = (0..100).map(|_| rand::random::<u64>()).collect();
+    xs.extend([0, alpha - 1, alpha, alpha + 1, u64::MAX]);
+
+    let (mut io0, mut io1) = LocalIO::new_pair();
+    let xs1 = xs.clone();
+    let handle = std::thread::spawn(move || {
+        let dcf = Dcf::new(bits);
+        let mut y = vec![Block::ZERO; xs1.len()];
+        dcf.eval_batch(&k1, &xs1, &mut y);
+        io1.send_block_vec(&y).unwrap();
+    });
+    let mut y = vec![Block::ZERO; xs.len()];
+    dcf.eval_batch(&k0, &xs, &mut y);
+    let other = io0.recv_block_vec(xs.len()).unwrap();
+    handle.join().unwrap();
+    for ((x, y), other) in xs.iter().zip(y.iter()).zip(other.iter()) {
+        assert_eq!(*y, dcf.eval(&k0, *x));
+        let expected = if *x < alpha { beta } else { Block::ZERO };
+        assert_eq!(*y ^ *other, expected);
+    }
+}
@@ -0,0 +1,203 @@
+//! Implement the two-party distributed point function (DPF) of Boyle, Gilboa and Ishai (<https://eprint.iacr.org/2018/707>, Figure 1).\
+//! The GGM tree is expanded with `TwoKeyPrp` as in `GgmTree`, and the control bit of a node is the least significant bit of its value.
+
+use crate::{
+    aes::{Aes, AES_BATCH},
+    prg::Prg,
+    tkprp::TwoKeyPrp,
+    Block,
+};
+
+/// The maximum depth of a DPF, i.e., the domain size is at most `2^32`.
+pub const MAX_DPF_DEPTH: usize = 32;
+
+/// The key of a party in a DPF.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DpfKey {
+    /// The root seed, whose least significant bit is the control bit of the party.
+    pub seed: Block,
+
+    /// The correction words of the seeds in each level, whose least significant bits are zero.
+    pub cw_seeds: Vec<Block>,
+
+    /// The correction words of the control bits of the left and the right children in each level.
+    pub cw_bits: Vec<[bool; 2]>,
+
+    /// The correction word of the output.
+    pub cw_out: Block,
+}
+
+impl DpfKey {
+    // The correction words of the left and the right children in the `h`-th level.
+    #[inline(always)]
+    fn cw(&self, h: usize) -> [Block; 2] {
+        let [t0, t1] = self.cw_bits[h];
+        [
+            self.cw_seeds[h] | Block::from(t0 as u128),
+            self.cw_seeds[h] | Block::from(t1 as u128),
+        ]
+    }
+}
+
+/// Struct of DPF with the domain `[0, 2^depth)` and outputs in `Block`.\
+/// The point function `f_{alpha, beta}` outputs `beta` on `alpha` and `Block::ZERO` elsewhere,
+/// and the outputs of the two keys on every point are XOR shares of it.
+pub struct Dpf {
+    tkprp: TwoKeyPrp,
+    aes: Aes,
+    depth: usize,
+}
+
+impl Dpf {
+    /// New a DPF with the domain size `2^depth`, where `depth <= MAX_DPF_DEPTH`.
+    #[inline(always)]
+    pub fn new(depth: usize) -> Self {
+        assert!(depth <= MAX_DPF_DEPTH);
+        Self {
+            tkprp: TwoKeyPrp::new([Block::ZERO, Block::from(1u128)]),
+            aes: Aes::new(Block::from(2u128)),
+            depth,
+        }
+    }
+
+    /// Get the depth.
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    // Convert a leaf into an output.
+    #[inline(always)]
+    fn convert(&self, s: Block) -> Block {
+        self.aes.encrypt_block(s) ^ s
+    }
+
+    /// Generate the keys of the two parties for the point function `f_{alpha, beta}`.
+    pub fn gen(&self, alpha: u32, beta: Block, prg: &mut Prg) -> (DpfKey, DpfKey) {
+        assert!((alpha as u64) < 1 << self.depth);
+        let mut s = [prg.random_block(), prg.random_block()];
+        s[0] &= Block::from(!1u128);
+        s[1].set_lsb();
+        let seeds = s;
+
+        let mut cw_seeds = Vec::with_capacity(self.depth);
+        let mut cw_bits = Vec::with_capacity(self.depth);
+        let mut buf = [Block::ZERO; 4];
+        for h in 0..self.depth {
+            let a = ((alpha >> (self.depth - 1 - h)) & 1) as usize;
+            // `buf` is `[s0_L, s0_R, s1_L, s1_R]`.
+            self.tkprp.expand_2to4(&mut buf, &s);
+            let cw = (buf[a ^ 1] ^ buf[2 + (a ^ 1)]) & Block::from(!1u128);
+            let t = [
+                buf[0].get_lsb() ^ buf[2].get_lsb() ^ (a == 0),
+                buf[1].get_lsb() ^ buf[3].get_lsb() ^ (a == 1),
+            ];
+            let cw_keep = cw | Block::from(t[a] as u128);
+            for (b, s) in s.iter_mut().enumerate() {
+                *s = buf[2 * b + a] ^ (cw_keep & Block::SELECT_MASK[s.get_lsb() as usize]);
+            }
+            cw_seeds.push(cw);
+            cw_bits.push(t);
+        }
+        let cw_out = beta ^ self.convert(s[0]) ^ self.convert(s[1]);
+
+        let key = |seed| DpfKey {
+            seed,
+            cw_seeds: cw_seeds.clone(),
+            cw_bits: cw_bits.clone(),
+            cw_out,
+        };
+        (key(seeds[0]), key(seeds[1]))
+    }
+
+    /// Evaluate the share of `f_{alpha, beta}(x)` with a key.
+    pub fn eval(&self, key: &DpfKey, x: u32) -> Block {
+        assert!((x as u64) < 1 << self.depth);
+        assert_eq!(key.cw_seeds.len(), self.depth);
+        let mut s = key.seed;
+        for h in 0..self.depth {
+            let b = ((x >> (self.depth - 1 - h)) & 1) as usize;
+            let mask = Block::SELECT_MASK[s.get_lsb() as usize];
+            s = self.tkprp.expand_child(s, b) ^ (key.cw(h)[b] & mask);
+        }
+        self.convert(s) ^ (key.cw_out & Block::SELECT_MASK[s.get_lsb() as usize])
+    }
+
+    /// Evaluate the shares of `f_{alpha, beta}` on the full domain with a key.\
+    /// Output: `out`: `out[x]` is the share of `f_{alpha, beta}(x)`, with size `2^depth`.
+    pub fn eval_all(&self, key: &DpfKey, out: &mut [Block]) {
+        assert_eq!(out.len(), 1 << self.depth);
+        assert_eq!(key.cw_seeds.len(), self.depth);
+
+        let mut buf = [Block::ZERO; 8];
+        out[0] = key.seed;
+        for h in 0..self.depth {
+            let cw = key.cw(h);
+            let sz = 1 << h;
+            // Expand the parents from the end, such that each parent is read before it is overwritten.
+            let step = if sz < 4 { 1 } else { 4 };
+            for i in (0..sz).step_by(step).rev() {
+                let mut parents = [Block::ZERO; 4];
+                parents[..step].copy_from_slice(&out[i..i + step]);
+                if step == 1 {
+                    self.tkprp.expand_1to2(&mut buf, parents[0]);
+                } else {
+                    self.tkprp.expand_4to8(&mut buf, &parents);
+                }
+                for (c, p) in buf.chunks_exact_mut(2).zip(parents[..step].iter()) {
+                    let mask = Block::SELECT_MASK[p.get_lsb() as usize];
+                    c[0] ^= cw[0] & mask;
+                    c[1] ^= cw[1] & mask;
+                }
+                out[2 * i..2 * (i + step)].copy_from_slice(&buf[..2 * step]);
+            }
+        }
+
+        let mut tmp = [Block::ZERO; AES_BATCH];
+        for chunk in out.chunks_mut(AES_BATCH) {
+            let tmp = &mut tmp[..chunk.len()];
+            tmp.copy_from_slice(chunk);
+            self.aes.encrypt_block_slice(tmp);
+            for (y, t) in chunk.iter_mut().zip(tmp.iter()) {
+                *y ^= *t ^ (key.cw_out & Block::SELECT_MASK[y.get_lsb() as usize]);
+            }
+        }
+    }
+}
+
+#[test]
+fn dpf_test() {
+    let mut prg = Prg::new();
+    for depth in [0, 1, 2, 3, 10] {
+        let dpf = Dpf::new(depth);
+        let alpha = prg.random_below(1 << depth) as u32;
+        let beta = prg.random_block();
+        let (k0, k1) = dpf.gen(alpha, beta, &mut prg);
+
+        let mut y0 = vec![Block::ZERO; 1 << depth];
+        let mut y1 = vec![Block::ZERO; 1 << depth];
+        dpf.eval_all(&k0, &mut y0);
+        dpf.eval_all(&k1, &mut y1);
+        for (x, (a, b)) in y0.iter().zip(y1.iter()).enumerate() {
+            let expected = if x == alpha as usize {
+                beta
+            } else {
+                Block::ZERO
+            };
+            assert_eq!(*a ^ *b, expected);
+            assert_eq!(dpf.eval(&k0, x as u32), *a);
+            assert_eq!(dpf.eval(&k1, x as u32), *b);
+        }
+    }
+
+    // The largest domain is evaluated on a few points.
+    let dpf = Dpf::new(MAX_DPF_DEPTH);
+    let alpha = u32::MAX - 5;
+    let beta = prg.random_block();
+    let (k0, k1) = dpf.gen(alpha, beta, &mut prg);
+    for x in [0, 1, alpha - 1, alpha, alpha + 1, u32::MAX] {
+        let y = dpf.eval(&k0, x) ^ dpf.eval(&k1, x);
+        assert_eq!(y, if x == alpha { beta } else { Block::ZERO });
+    }
+}
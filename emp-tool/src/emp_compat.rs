@@ -0,0 +1,348 @@
+//! Implement the layouts of the C++ EMP toolkit (emp-tool and emp-ot), such that a Rust party can talk to a C++ party.\
+//! The functions of this crate that already match C++ bit for bit are documented here rather than duplicated.
+//!
+//! - PRG: `emp::PRG` outputs `AES_{seed xor makeBlock(0, id)}(makeBlock(0, counter))`, which is the block stream of `Prg::from_seed(seed ^ Block::from([id, 0]))`.
+//!   The streams only differ in how partial blocks are consumed, which `CppPrg` follows.
+//! - GGM: `GgmTree` uses the keys `zero_block` and `makeBlock(0, 1)` of `TwoKeyPRP` in emp-ot, and the same node expansion.
+//!   A C++ tree of depth `d` has `2^{d-1}` leaves, i.e., it is `GgmTree::new(d - 1)`, see `cpp_ggm_tree`.
+//! - LPN: `LpnF2` in emp-ot samples indices from `makeBlock(row, i)`, which differs from `Lpn`, see `cpp_lpn_compute`.
+//! - Bool vectors: `IOChannel::send_bool` sends the bools before the first 8-byte boundary of the array one byte each, packs each following full group of 8 bools into a byte,
+//!   and sends the remaining bools one byte each, see `pack_bools`. `NetIO` writes these bytes to the socket as they are, without any framing.
+
+use crate::{ggm_tree::GgmTree, io_channel::IOChannel, prg::Prg, prp::Prp, Block};
+use rand_core::SeedableRng;
+use std::io::Result;
+
+/// The PRG of the C++ EMP toolkit.
+pub struct CppPrg(Prg);
+
+impl CppPrg {
+    /// New a PRG as `PRG(&seed, id)` in C++.
+    #[inline(always)]
+    pub fn new(seed: Block, id: u64) -> Self {
+        Self(Prg::from_seed(seed ^ Block::from([id, 0])))
+    }
+
+    /// Fill a block slice as `random_block` in C++.
+    #[inline(always)]
+    pub fn random_block(&mut self, buf: &mut [Block]) {
+        self.0.random_blocks(buf);
+    }
+
+    /// Fill a byte slice as `random_data` in C++.\
+    /// If the length is not a multiple of 16, the rest of the last block is discarded.
+    #[inline(always)]
+    pub fn random_data(&mut self, buf: &mut [u8]) {
+        let mut chunks = buf.chunks_exact_mut(16);
+        for chunk in chunks.by_ref() {
+            chunk.copy_from_slice(self.0.random_block().as_ref());
+        }
+        let rem = chunks.into_remainder();
+        if !rem.is_empty() {
+            let len = rem.len();
+            rem.copy_from_slice(&self.0.random_block().as_ref()[..len]);
+        }
+    }
+
+    /// Get the counter, i.e., the number of blocks that are output.
+    #[inline(always)]
+    pub fn counter(&self) -> u64 {
+        self.0.counter()
+    }
+}
+
+/// New a GGM tree that matches the C++ GGM tree of emp-ot with `depth`, which has `2^{depth-1}` leaves.\
+/// `SPCOT_Sender::ggm_tree_gen` in C++ also clears the least significant bit of each leaf after the expansion, which is left to the caller.
+#[inline(always)]
+pub fn cpp_ggm_tree(depth: usize) -> GgmTree {
+    assert!(depth >= 2);
+    GgmTree::new(depth - 1)
+}
+
+/// The number of non-zero values in each row of the C++ LPN matrix.
+pub const CPP_LPN_D: usize = 10;
+
+/// Compute `y = Ax + y` as `LpnF2::compute` in emp-ot with one thread, where `A` is defined by `seed` and has `k = x.len()` columns.\
+/// Each group of 4 rows starting from row `i` samples its `4 * CPP_LPN_D` indices from `AES_seed(makeBlock(i, 0..10))`,
+/// and each remaining row `i` samples its `CPP_LPN_D` indices from `AES_seed(makeBlock(i, 0..3))`.
+/// The groups stop before the last 4 rows as the C++ loop is `for (; j < end - 4; j += 4)`, e.g., the last 4 rows are computed one by one if `y.len()` is a multiple of 4.
+pub fn cpp_lpn_compute(seed: Block, y: &mut [Block], x: &[Block]) {
+    let k = x.len() as u32;
+    assert!(x.len() >= CPP_LPN_D);
+    let mut mask = 1;
+    while mask < k {
+        mask <<= 1;
+        mask |= 0x1;
+    }
+    let prp = Prp::new(seed);
+    let index = |ind: u32| {
+        let ind = u32::from_le(ind) & mask;
+        (if ind >= k { ind - k } else { ind }) as usize
+    };
+
+    let mut i = 0;
+    while i + 4 < y.len() {
+        let tmp = std::array::from_fn::<_, CPP_LPN_D, _>(|m| Block::from([m as u64, i as u64]));
+        let tmp = prp.permute_many_blocks(tmp);
+        let r: &[u32] = bytemuck::cast_slice(&tmp);
+        for (row, r) in y[i..i + 4].iter_mut().zip(r.chunks_exact(CPP_LPN_D)) {
+            for &ind in r {
+                *row ^= x[index(ind)];
+            }
+        }
+        i += 4;
+    }
+
+    for (i, row) in y.iter_mut().enumerate().skip(i) {
+        let tmp = std::array::from_fn::<_, 3, _>(|m| Block::from([m as u64, i as u64]));
+        let tmp = prp.permute_many_blocks(tmp);
+        let r: &[u32] = bytemuck::cast_slice(&tmp);
+        for &ind in r[..CPP_LPN_D].iter() {
+            *row ^= x[index(ind)];
+        }
+    }
+}
+
+/// The number of bools that C++ `send_bool` sends one byte each before the packed groups, where `offset` is the address of the C++ bool array modulo 8.\
+/// If the array does not contain a full aligned group of 8 bools, `std::align` fails and all `len` bools are sent one byte each.
+#[inline(always)]
+fn bools_prefix_len(len: usize, offset: usize) -> usize {
+    assert!(offset < 8);
+    let diff = (8 - offset) % 8;
+    if diff + 8 > len {
+        len
+    } else {
+        diff
+    }
+}
+
+/// Pack bools as `send_bool` in C++, where `offset` is the address of the C++ bool array modulo 8, e.g., `0` for heap allocations.\
+/// The bools before the first 8-byte boundary take a byte each, each following full group of 8 bools is packed into a byte from the least significant bit,
+/// and each of the remaining bools takes a byte.
+pub fn pack_bools(bits: &[bool], offset: usize) -> Vec<u8> {
+    let (prefix, bits) = bits.split_at(bools_prefix_len(bits.len(), offset));
+    let mut bytes: Vec<u8> = prefix.iter().map(|&b| b as u8).collect();
+    let mut chunks = bits.chunks_exact(8);
+    bytes.extend(chunks.by_ref().map(|chunk| {
+        chunk
+            .iter()
+            .enumerate()
+            .fold(0u8, |acc, (i, &b)| acc | ((b as u8) << i))
+    }));
+    bytes.extend(chunks.remainder().iter().map(|&b| b as u8));
+    bytes
+}
+
+/// Unpack `len` bools packed by `pack_bools` or by `send_bool` in C++ with the same `offset`.
+pub fn unpack_bools(bytes: &[u8], len: usize, offset: usize) -> Vec<bool> {
+    assert_eq!(bytes.len(), packed_bools_len(len, offset));
+    let diff = bools_prefix_len(len, offset);
+    let groups = (len - diff) / 8;
+    let mut bits = Vec::with_capacity(len);
+    bits.extend(bytes[..diff].iter().map(|&b| b != 0));
+    for &byte in bytes[diff..diff + groups].iter() {
+        bits.extend((0..8).map(|i| (byte >> i) & 1 == 1));
+    }
+    bits.extend(bytes[diff + groups..].iter().map(|&b| b != 0));
+    bits
+}
+
+/// The number of bytes of `len` bools packed by `pack_bools` with `offset`.
+#[inline(always)]
+pub fn packed_bools_len(len: usize, offset: usize) -> usize {
+    let diff = bools_prefix_len(len, offset);
+    let rest = len - diff;
+    diff + rest / 8 + rest % 8
+}
+
+/// Send bools to a C++ party, which receives them with `recv_bool` into an array whose address modulo 8 is `offset`.
+#[inline(always)]
+pub fn send_bools<IO: IOChannel>(io: &mut IO, bits: &[bool], offset: usize) -> Result<()> {
+    io.send_bytes(&pack_bools(bits, offset))
+}
+
+/// Receive `len` bools from a C++ party, which sends them with `send_bool` from an array whose address modulo 8 is `offset`.
+#[inline(always)]
+pub fn recv_bools<IO: IOChannel>(io: &mut IO, len: usize, offset: usize) -> Result<Vec<bool>> {
+    let mut bytes = vec![0u8; packed_bools_len(len, offset)];
+    io.recv_bytes(&mut bytes)?;
+    Ok(unpack_bools(&bytes, len, offset))
+}
+
+// The test vectors are printed by `cpp/emp_compat_vectors.cpp`.
+
+#[test]
+fn cpp_prg_test() {
+    let seed = Block::from(0x0123456789abcdeffedcba9876543210);
+    let mut prg = CppPrg::new(seed, 7);
+    let mut blks = [Block::ZERO; 3];
+    prg.random_block(&mut blks);
+    assert_eq!(
+        blks,
+        [
+            0xcf40d7f6d882ab50ccaccac2162056ea,
+            0xdb8c31f7303f00415be2a4200a567140,
+            0x44b002dc02380cc1861947cacc537dcd,
+        ]
+        .map(Block::from)
+    );
+
+    // A partial block is discarded.
+    let mut bytes = [0u8; 20];
+    prg.random_data(&mut bytes);
+    assert_eq!(
+        bytes,
+        [
+            0x33, 0x9f, 0x54, 0xd7, 0xeb, 0xde, 0xfc, 0x2d, 0x21, 0x81, 0x79, 0x4c, 0x2f, 0x8b,
+            0x69, 0x33, 0x79, 0x8b, 0x4f, 0x56,
+        ]
+    );
+    assert_eq!(prg.counter(), 5);
+    prg.random_block(&mut blks[..1]);
+    assert_eq!(blks[0], Block::from(0x3e9967c86dd44b340476d7b6e7e95f3d));
+}
+
+#[test]
+fn cpp_ggm_test() {
+    let depth = 4;
+    let ggm = cpp_ggm_tree(depth);
+    let mut tree = vec![Block::ZERO; 1 << (depth - 1)];
+    let mut k0 = vec![Block::ZERO; depth - 1];
+    let mut k1 = vec![Block::ZERO; depth - 1];
+    ggm.gen(
+        Block::from(0x11111111111111112222222222222222),
+        &mut tree,
+        &mut k0,
+        &mut k1,
+    );
+    let expected: Vec<Block> = [
+        0x18adc22d3d631e684aa18a6bbfcaa8f6,
+        0x9598da3b3b9c2517edeb3b523dd26588,
+        0xeaf2b00aad1a1ba9b9b8c45b0327d5d9,
+        0x2ea2b4000469bb2594980869d241799d,
+        0x9901b9b589287ae324b6ca85663317a9,
+        0x16dfed3a569328f8ab0b0ed77dd91bc8,
+        0x83b5ef2c346063c42d9cbb1993cd0e9f,
+        0x6ee37c0a1967f9b227f389d7da9bc360,
+    ]
+    .map(Block::from)
+    .to_vec();
+    // The leaves are compared without their least significant bits, which C++ clears.
+    let mask = Block::from([u64::MAX - 1, u64::MAX]);
+    let tree: Vec<Block> = tree.iter().map(|&b| b & mask).collect();
+    let expected: Vec<Block> = expected.iter().map(|&b| b & mask).collect();
+    assert_eq!(tree, expected);
+    let e0: Vec<Block> = [
+        0x7b8cf6956774f16844f50c0765a61f7d,
+        0xb87d8815c6126c88b354fb7396beae67,
+        0xe8eb24be2d311ce6fa333fac49136419,
+    ]
+    .map(Block::from)
+    .to_vec();
+    let e1: Vec<Block> = [
+        0x322dd3eee562b55e39e17e3b78505df8,
+        0xec2869ae9ed136dbf17832ec4d13e397,
+        0xc306ff0b70014f78f58bb43b48d1c4bd,
+    ]
+    .map(Block::from)
+    .to_vec();
+    assert_eq!(k0, e0);
+    assert_eq!(k1, e1);
+}
+
+#[test]
+fn cpp_lpn_test() {
+    // `x` and `y` are the first 37 and the next 10 blocks of `PRG(&x_seed)`.
+    // Rows `[0, 8)` are in groups of 4, and rows `[8, 10)` are computed one by one.
+    let mut prg = CppPrg::new(Block::from([4, 3]), 0);
+    let mut x = vec![Block::ZERO; 37];
+    let mut y = vec![Block::ZERO; 10];
+    prg.random_block(&mut x);
+    prg.random_block(&mut y);
+    cpp_lpn_compute(Block::from([6, 5]), &mut y, &x);
+    let expected: Vec<Block> = [
+        0x514b2fbc9218cd2644fe5d86eed85629,
+        0x76e776d5267057623c41a3ad5b4046d1,
+        0x7e5a8c010663a6721ba2d53165c6664d,
+        0x56ddf82bbefd1efbac6cee1c0beb2932,
+        0xb12b66b793cbd3fe8a8d75198b2ed7fe,
+        0x3a9844f17913c8c82c0d550bb210eb67,
+        0x9c278cb2844f24775d38421788429347,
+        0xe906a3b84a8eb09c97e4c477316cff61,
+        0x4fbfc4f8f4bb49c2f083e7e2399dff29,
+        0x95d54ad27e0210a40a386a98273814e6,
+    ]
+    .map(Block::from)
+    .to_vec();
+    assert_eq!(y, expected);
+}
+
+#[test]
+fn cpp_bools_test() {
+    // `(offset, len, bytes)`, where bool `i` is true iff `i % 3 == 0 || i % 5 == 0`.
+    for (offset, len, expected) in cpp_bools_vectors() {
+        let bits: Vec<bool> = (0..len).map(|i| i % 3 == 0 || i % 5 == 0).collect();
+        let bytes = pack_bools(&bits, offset);
+        assert_eq!(bytes, expected);
+        assert_eq!(packed_bools_len(len, offset), bytes.len());
+        assert_eq!(unpack_bools(&bytes, len, offset), bits);
+    }
+
+    let bits = [true, false, true, true, false, false, true, false];
+    assert_eq!(
+        pack_bools(&bits, 0),
+        crate::utils::pack_bits_to_bytes(&bits)
+    );
+}
+
+#[test]
+fn cpp_bools_net_io_test() {
+    use crate::io_channel::NetIO;
+
+    let addr = "127.0.0.1:12348";
+    let handle = std::thread::spawn(move || {
+        let mut io = NetIO::new(true, addr).unwrap();
+        for (offset, len, _) in cpp_bools_vectors() {
+            let bits: Vec<bool> = (0..len).map(|i| i % 3 == 0 || i % 5 == 0).collect();
+            send_bools(&mut io, &bits, offset).unwrap();
+            send_bools(&mut io, &bits, offset).unwrap();
+        }
+        io.flush().unwrap();
+    });
+
+    // The bytes on the wire are exactly the bytes written by `NetIO::send_bool` in C++.
+    let mut io = NetIO::new(false, addr).unwrap();
+    for (offset, len, expected) in cpp_bools_vectors() {
+        let mut received = vec![0u8; expected.len()];
+        io.recv_bytes(&mut received).unwrap();
+        assert_eq!(received, expected);
+        let bits: Vec<bool> = (0..len).map(|i| i % 3 == 0 || i % 5 == 0).collect();
+        assert_eq!(recv_bools(&mut io, len, offset).unwrap(), bits);
+    }
+    handle.join().unwrap();
+}
+
+// The bytes of `send_bool(buf + offset, len)` in C++, where `buf` is 8-byte aligned.
+#[cfg(test)]
+fn cpp_bools_vectors() -> [(usize, usize, Vec<u8>); 4] {
+    [
+        // Aligned: 2 packed groups and 3 remaining bools.
+        (0, 19, vec![0x69, 0x96, 0x00, 0x00, 0x01]),
+        // 5 bools before the boundary, 1 packed group and 6 remaining bools.
+        (
+            3,
+            19,
+            vec![
+                0x01, 0x00, 0x00, 0x01, 0x00, 0xb3, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01,
+            ],
+        ),
+        // No full aligned group, so `std::align` fails and every bool takes a byte.
+        (5, 8, vec![0x01, 0x00, 0x00, 0x01, 0x00, 0x01, 0x01, 0x00]),
+        // 1 bool before the boundary, 2 packed groups and 7 remaining bools.
+        (
+            7,
+            24,
+            vec![0x01, 0x34, 0x4b, 0x00, 0x01, 0x00, 0x01, 0x01, 0x00, 0x00],
+        ),
+    ]
+}
@@ -1,10 +1,21 @@
 //! Implement GGM tree for OT.
-use crate::{tkprp::TwoKeyPrp, Block};
+use crate::{
+    aes::AES_BATCH,
+    tkprp::{MultiKeyPrp, TwoKeyPrp},
+    Block,
+};
+use rayon::prelude::*;
 
-/// Struct of GGM
+/// The number of trees whose levels are expanded together by `GgmTree::gen_many` and `GgmTree::reconstruct_many`.
+const GROUP_SIZE: usize = 4;
+
+/// Struct of GGM.\
+/// A tree with `n` leaves has `depth = ceil(log2(n))`, and only keeps the first `n` leaves of the full tree.
+/// The `h`-th level only keeps the nodes that are ancestors of these leaves, and the other nodes are not expanded.
 pub struct GgmTree {
     tkprp: TwoKeyPrp,
     depth: usize,
+    n: usize,
 }
 
 impl GgmTree {
@@ -12,19 +23,66 @@ impl GgmTree {
     #[inline(always)]
     pub fn new(depth: usize) -> Self {
         let tkprp = TwoKeyPrp::new([Block::ZERO, Block::from(1u128)]);
-        Self { tkprp, depth }
+        Self {
+            tkprp,
+            depth,
+            n: 1 << depth,
+        }
+    }
+
+    /// New GgmTree instance with `n >= 2` leaves, where `n` is not necessarily a power of two.
+    #[inline(always)]
+    pub fn with_leaves(n: usize) -> Self {
+        assert!(n >= 2);
+        let mut ggm = Self::new(n.next_power_of_two().trailing_zeros() as usize);
+        ggm.n = n;
+        ggm
+    }
+
+    /// Get the depth of the tree, i.e., the number of levels below the root.
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get the number of leaves.
+    #[inline(always)]
+    pub fn leaves(&self) -> usize {
+        self.n
+    }
+
+    // Check whether the tree is a full tree handled by the unpruned expansion.
+    #[inline(always)]
+    fn is_full(&self) -> bool {
+        self.depth >= 2 && self.n == 1 << self.depth
+    }
+
+    // The number of nodes kept in the `h`-th level, where the root is in the 0-th level.
+    #[inline(always)]
+    fn level_size(&self, h: usize) -> usize {
+        let s = self.depth - h;
+        (self.n + (1 << s) - 1) >> s
     }
 
     /// Input: `seed`: a seed.\
-    /// Output: `tree`: a GGM (binary tree) `tree`, with size `2^{depth}`.\
+    /// Output: `tree`: a GGM (binary tree) `tree`, with size `n`.\
     /// Output: `k0`: XORs of all the left-node values in each level, with size `depth`.\
     /// Output: `k1`: XORs of all the right-node values in each level, with size `depth`.\
     /// This implementation is adopted from EMP Toolkit.
     pub fn gen(&self, seed: Block, tree: &mut [Block], k0: &mut [Block], k1: &mut [Block]) {
-        assert_eq!(tree.len(), 1 << (self.depth));
+        assert_eq!(tree.len(), self.n);
         assert_eq!(k0.len(), self.depth);
         assert_eq!(k1.len(), self.depth);
 
+        if !self.is_full() {
+            tree[0] = seed;
+            for h in 0..self.depth {
+                (k0[h], k1[h]) =
+                    self.expand_pruned(tree, self.level_size(h), self.level_size(h + 1));
+            }
+            return;
+        }
+
         let mut buf = [Block::ZERO; 8];
         self.tkprp.expand_1to2(tree, seed);
         k0[0] = tree[0];
@@ -35,6 +93,13 @@ impl GgmTree {
         k1[1] = buf[1] ^ buf[3];
         tree[0..4].copy_from_slice(&buf[0..4]);
 
+        self.gen_levels(tree, k0, k1);
+    }
+
+    // Expand the levels from the 3rd one, given the first two levels in `tree[0..4]`.
+    #[inline(always)]
+    fn gen_levels(&self, tree: &mut [Block], k0: &mut [Block], k1: &mut [Block]) {
+        let mut buf = [Block::ZERO; 8];
         for h in 2..self.depth {
             k0[h] = Block::ZERO;
             k1[h] = Block::ZERO;
@@ -55,49 +120,293 @@ impl GgmTree {
         }
     }
 
+    // Expand the `parents` nodes in `tree[..parents]` into the first `children` nodes of the next level in `tree[..children]`,
+    // and return the XORs of the left and the right children.
+    fn expand_pruned(&self, tree: &mut [Block], parents: usize, children: usize) -> (Block, Block) {
+        let mut buf = [Block::ZERO; 8];
+        let mut sums = (Block::ZERO, Block::ZERO);
+        // The parents in `[0, full)` have both children, and are expanded 4 by 4.
+        let full = children / 8 * 4;
+        for i in (full..parents).rev() {
+            self.tkprp.expand_1to2(&mut buf, tree[i]);
+            tree[2 * i] = buf[0];
+            sums.0 ^= buf[0];
+            if 2 * i + 1 < children {
+                tree[2 * i + 1] = buf[1];
+                sums.1 ^= buf[1];
+            }
+        }
+        for i in (0..full).step_by(4).rev() {
+            self.tkprp.expand_4to8(&mut buf, &tree[i..]);
+            for c in buf.chunks_exact(2) {
+                sums.0 ^= c[0];
+                sums.1 ^= c[1];
+            }
+            tree[2 * i..2 * i + 8].copy_from_slice(&buf);
+        }
+        sums
+    }
+
+    /// Generate a GGM tree for each seed, which is the same as calling `gen` for each seed with multiple threads.\
+    /// Input: `seeds`: `t` seeds.\
+    /// Output: `trees`: `t` GGM trees laid out contiguously, with size `t * n`.\
+    /// Output: `k0`: the `k0` of each tree laid out contiguously, with size `t * depth`.\
+    /// Output: `k1`: the `k1` of each tree laid out contiguously, with size `t * depth`.\
+    /// Every 4 full trees are expanded together level by level, where each AES batch takes the nodes of all of them.
+    pub fn gen_many(
+        &self,
+        seeds: &[Block],
+        trees: &mut [Block],
+        k0: &mut [Block],
+        k1: &mut [Block],
+    ) {
+        let n = self.n;
+        let depth = self.depth;
+        assert_eq!(trees.len(), seeds.len() * n);
+        assert_eq!(k0.len(), seeds.len() * depth);
+        assert_eq!(k1.len(), seeds.len() * depth);
+
+        trees
+            .par_chunks_mut(GROUP_SIZE * n)
+            .zip(k0.par_chunks_mut(GROUP_SIZE * depth))
+            .zip(k1.par_chunks_mut(GROUP_SIZE * depth))
+            .zip(seeds.par_chunks(GROUP_SIZE))
+            .for_each(|(((trees, k0), k1), seeds)| {
+                if seeds.len() < GROUP_SIZE || !self.is_full() {
+                    for (((tree, k0), k1), seed) in trees
+                        .chunks_exact_mut(n)
+                        .zip(k0.chunks_exact_mut(depth))
+                        .zip(k1.chunks_exact_mut(depth))
+                        .zip(seeds)
+                    {
+                        self.gen(*seed, tree, k0, k1);
+                    }
+                    return;
+                }
+
+                let mut group = Self::group(trees, n);
+                for (tree, seed) in group.iter_mut().zip(seeds) {
+                    tree[0] = *seed;
+                }
+                for h in 0..depth {
+                    let sums = match h {
+                        0 => self.expand_group::<GROUP_SIZE>(&mut group, 1),
+                        1 => self.expand_group::<{ 2 * GROUP_SIZE }>(&mut group, 2),
+                        _ => self.expand_group::<{ 4 * GROUP_SIZE }>(&mut group, 1 << h),
+                    };
+                    for (j, [s0, s1]) in sums.into_iter().enumerate() {
+                        k0[j * depth + h] = s0;
+                        k1[j * depth + h] = s1;
+                    }
+                }
+            });
+    }
+
+    // Split `GROUP_SIZE` trees laid out contiguously into the trees.
+    #[inline(always)]
+    fn group(trees: &mut [Block], n: usize) -> [&mut [Block]; GROUP_SIZE] {
+        let mut trees = trees.chunks_exact_mut(n);
+        std::array::from_fn(|_| trees.next().unwrap())
+    }
+
+    // Expand the first `sz` nodes of each tree in `group` into the first `2 * sz` nodes, and return the XORs of the
+    // left and the right children of each tree. Each AES batch takes `N / GROUP_SIZE` consecutive parents of every tree.
+    fn expand_group<const N: usize>(
+        &self,
+        group: &mut [&mut [Block]; GROUP_SIZE],
+        sz: usize,
+    ) -> [[Block; 2]; GROUP_SIZE] {
+        let step = N / GROUP_SIZE;
+        assert!(sz.is_multiple_of(step));
+        let mut parents = [Block::ZERO; N];
+        let mut children = [[Block::ZERO; N]; 2];
+        let children = children.as_flattened_mut();
+        let mut sums = [[Block::ZERO; 2]; GROUP_SIZE];
+        // Expand the parents from the end, such that each parent is read before it is overwritten.
+        for i in (0..sz).step_by(step).rev() {
+            for (p, tree) in parents.chunks_exact_mut(step).zip(group.iter()) {
+                p.copy_from_slice(&tree[i..i + step]);
+            }
+            self.tkprp.expand_many(children, &parents);
+            for ((c, tree), sums) in children
+                .chunks_exact(2 * step)
+                .zip(group.iter_mut())
+                .zip(sums.iter_mut())
+            {
+                for c in c.chunks_exact(2) {
+                    sums[0] ^= c[0];
+                    sums[1] ^= c[1];
+                }
+                tree[2 * i..2 * (i + step)].copy_from_slice(c);
+            }
+        }
+        sums
+    }
+
     /// Reconstruct the GGM tree except the value in a given position.\
     /// Input : `k` - a slice of blocks with length `depth`, the values of k are chosen via OT from k0 and k1. \
     /// For the i-th value, if alpha[i] == 1, k[i] = k1[i]; else k[i] = k0[i].\
     /// Input : `alpha` - a slice of bits with length `depth`.\
     /// Output : `tree` - the ggm tree, except `tree[pos] == Block::ZERO`. \
-    /// The bit decomposition of `pos` is the complement of `alpha`. I.e., `pos[i] = 1 xor alpha[i]`.\
+    /// The bit decomposition of `pos` is the complement of `alpha`. I.e., `pos[i] = 1 xor alpha[i]`, and `pos` must be less than `n`.\
     pub fn reconstruct(&self, alpha: &[bool], k: &[Block], tree: &mut [Block]) {
+        if !self.is_full() {
+            return self.reconstruct_pruned(alpha, k, tree);
+        }
+
         let mut pos = 0;
         for i in 1..=self.depth {
-            pos *= 2;
-            tree[pos] = Block::ZERO;
-            tree[pos + 1] = Block::ZERO;
-            if !alpha[i - 1] {
-                self.reconstruct_layer(i, false, pos, k[i - 1], tree);
-                pos += 1;
-            } else {
-                self.reconstruct_layer(i, true, pos + 1, k[i - 1], tree);
+            self.reconstruct_node(i, alpha[i - 1], k[i - 1], &mut pos, tree);
+            if i < self.depth {
+                self.reconstruct_layer(i, tree);
             }
         }
     }
 
-    // Handle each layer.
-    fn reconstruct_layer(
+    // Reconstruct a tree that is not full, where the sibling of a punctured node may not be kept.
+    fn reconstruct_pruned(&self, alpha: &[bool], k: &[Block], tree: &mut [Block]) {
+        let pos = alpha[..self.depth]
+            .iter()
+            .fold(0, |pos, &a| (pos << 1) | !a as usize);
+        self.expand_punctured_with(pos, tree, |h, level, sibling| {
+            let mut sum = k[h - 1];
+            for x in level[sibling & 1..].iter().step_by(2) {
+                sum ^= *x;
+            }
+            sum
+        });
+    }
+
+    // Expand the tree except the leaf `pos` level by level, where `sibling(h, level, i)` returns the value of the node `i`
+    // in the `h`-th level that is the sibling of the punctured node, and the two nodes are zero in `level`.
+    fn expand_punctured_with(
         &self,
-        depth: usize,
-        left_or_right: bool,
         pos: usize,
-        k: Block,
         tree: &mut [Block],
+        mut sibling: impl FnMut(usize, &[Block], usize) -> Block,
     ) {
-        let sz = 1 << depth;
-        let mut sum = Block::ZERO;
-        let start = if left_or_right { 1 } else { 0 };
+        assert_eq!(tree.len(), self.n);
+        assert!(pos < self.n);
+
+        for h in 1..=self.depth {
+            let m = self.level_size(h);
+            let p = pos >> (self.depth - h);
+            tree[p] = Block::ZERO;
+            if p ^ 1 < m {
+                tree[p ^ 1] = Block::ZERO;
+                tree[p ^ 1] = sibling(h, &tree[..m], p ^ 1);
+            }
 
-        for i in (start..sz).step_by(2) {
-            sum ^= tree[i];
+            if h < self.depth {
+                self.expand_pruned(tree, m, self.level_size(h + 1));
+            }
         }
-        tree[pos] = sum ^ k;
+    }
 
-        if depth == (self.depth) {
-            return;
+    // Compute the nodes that are siblings of the path from `seed` to the leaf `pos`, from the top level to the leaves.
+    pub(crate) fn co_path(&self, seed: Block, pos: usize) -> Vec<Block> {
+        let mut buf = [Block::ZERO; 2];
+        let mut node = seed;
+        (0..self.depth)
+            .map(|h| {
+                let b = (pos >> (self.depth - 1 - h)) & 1;
+                self.tkprp.expand_1to2(&mut buf, node);
+                node = buf[b];
+                buf[b ^ 1]
+            })
+            .collect()
+    }
+
+    // Compute the leaf `pos` from a `node` in the `h`-th level that is on the path to it.
+    pub(crate) fn eval_path(&self, mut node: Block, h: usize, pos: usize) -> Block {
+        for h in h..self.depth {
+            node = self
+                .tkprp
+                .expand_child(node, (pos >> (self.depth - 1 - h)) & 1);
+        }
+        node
+    }
+
+    // Expand the tree except the leaf `pos` from the co-path of `pos`, where `tree[pos] == Block::ZERO`.
+    pub(crate) fn expand_co_path(&self, pos: usize, co_path: &[Block], tree: &mut [Block]) {
+        assert_eq!(co_path.len(), self.depth);
+        self.expand_punctured_with(pos, tree, |h, _, _| co_path[h - 1]);
+    }
+
+    /// Reconstruct many GGM trees, which is the same as calling `reconstruct` for each tree with multiple threads.\
+    /// Input : `alphas` - the `alpha` of each tree laid out contiguously, with size `t * depth`.\
+    /// Input : `ks` - the `k` of each tree laid out contiguously, with size `t * depth`.\
+    /// Output : `trees` - `t` GGM trees laid out contiguously, with size `t * n`.\
+    /// Every 4 full trees are expanded together level by level as in `gen_many`.
+    pub fn reconstruct_many(&self, alphas: &[bool], ks: &[Block], trees: &mut [Block]) {
+        let n = self.n;
+        let depth = self.depth;
+        let t = trees.len() / n;
+        assert_eq!(trees.len(), t * n);
+        assert_eq!(alphas.len(), t * depth);
+        assert_eq!(ks.len(), t * depth);
+
+        trees
+            .par_chunks_mut(GROUP_SIZE * n)
+            .zip(alphas.par_chunks(GROUP_SIZE * depth))
+            .zip(ks.par_chunks(GROUP_SIZE * depth))
+            .for_each(|((trees, alphas), ks)| {
+                if trees.len() < GROUP_SIZE * n || !self.is_full() {
+                    for ((tree, alpha), k) in trees
+                        .chunks_exact_mut(n)
+                        .zip(alphas.chunks_exact(depth))
+                        .zip(ks.chunks_exact(depth))
+                    {
+                        self.reconstruct(alpha, k, tree);
+                    }
+                    return;
+                }
+
+                let mut group = Self::group(trees, n);
+                let mut pos = [0; GROUP_SIZE];
+                for i in 1..=depth {
+                    for (j, (tree, pos)) in group.iter_mut().zip(pos.iter_mut()).enumerate() {
+                        let h = j * depth + i - 1;
+                        self.reconstruct_node(i, alphas[h], ks[h], pos, tree);
+                    }
+                    // The sums of the levels are not needed in the reconstruction.
+                    if i == 1 {
+                        self.expand_group::<{ 2 * GROUP_SIZE }>(&mut group, 2);
+                    } else if i < depth {
+                        self.expand_group::<{ 4 * GROUP_SIZE }>(&mut group, 1 << i);
+                    }
+                }
+            });
+    }
+
+    // Set the two nodes below the punctured node `pos` in the `(h-1)`-th level to zero, and recover the one that is
+    // not on the path from `k`. Then move `pos` to the punctured node in the `h`-th level.
+    #[inline(always)]
+    fn reconstruct_node(
+        &self,
+        h: usize,
+        alpha: bool,
+        k: Block,
+        pos: &mut usize,
+        tree: &mut [Block],
+    ) {
+        *pos *= 2;
+        tree[*pos] = Block::ZERO;
+        tree[*pos + 1] = Block::ZERO;
+        let sibling = *pos + alpha as usize;
+        let mut sum = Block::ZERO;
+        for x in tree[sibling & 1..1 << h].iter().step_by(2) {
+            sum ^= *x;
+        }
+        tree[sibling] = sum ^ k;
+        if !alpha {
+            *pos += 1;
         }
+    }
 
+    // Expand the `h`-th level.
+    fn reconstruct_layer(&self, h: usize, tree: &mut [Block]) {
+        let sz = 1 << h;
         let mut buf = [Block::ZERO; 8];
         if sz == 2 {
             self.tkprp.expand_2to4(&mut buf, tree);
@@ -111,6 +420,102 @@ impl GgmTree {
     }
 }
 
+/// Struct of q-ary GGM, where `q = 2^b`.\
+/// The children of the node `i` in a level are the nodes `q * i + j` for `j` in `[0, q)` in the next level,
+/// and they are expanded with the keys `[0, 1, ..., q - 1]` of `MultiKeyPrp`.
+/// With `q = 2`, the tree is the same as `GgmTree`.
+pub struct QaryGgmTree {
+    mkprp: MultiKeyPrp,
+    q: usize,
+    depth: usize,
+}
+
+impl QaryGgmTree {
+    ///New QaryGgmTree instance with arity `q` and `q^depth` leaves.
+    #[inline(always)]
+    pub fn new(q: usize, depth: usize) -> Self {
+        assert!(q >= 2 && q.is_power_of_two());
+        let mkprp = MultiKeyPrp::with_arity(q);
+        Self { mkprp, q, depth }
+    }
+
+    // Expand the `n` nodes in `tree[..n]` into their children in `tree[..q * n]`.
+    fn expand_level(&self, tree: &mut [Block], n: usize, buf: &mut [Block]) {
+        let q = self.q;
+        for start in (0..n).step_by(AES_BATCH).rev() {
+            let end = (start + AES_BATCH).min(n);
+            let buf = &mut buf[..end - start];
+            buf.copy_from_slice(&tree[start..end]);
+            self.mkprp.expand(&mut tree[q * start..q * end], buf);
+        }
+    }
+
+    /// Input: `seed`: a seed.\
+    /// Output: `tree`: a q-ary GGM `tree`, with size `q^{depth}`.\
+    /// Output: `sums`: with size `q * depth`, where `sums[h * q + j]` is the XOR of the `j`-th children of all nodes in the `(h+1)`-th level.
+    pub fn gen(&self, seed: Block, tree: &mut [Block], sums: &mut [Block]) {
+        let q = self.q;
+        assert_eq!(tree.len(), q.pow(self.depth as u32));
+        assert_eq!(sums.len(), q * self.depth);
+
+        let mut buf = vec![Block::ZERO; AES_BATCH.min(tree.len())];
+        tree[0] = seed;
+        let mut n = 1;
+        for sums in sums.chunks_exact_mut(q) {
+            self.expand_level(tree, n, &mut buf);
+            n *= q;
+            sums.fill(Block::ZERO);
+            for children in tree[..n].chunks_exact(q) {
+                for (s, c) in sums.iter_mut().zip(children) {
+                    *s ^= *c;
+                }
+            }
+        }
+    }
+
+    /// Reconstruct the q-ary GGM tree except the value in a given position.\
+    /// Input : `alpha` - a slice of digits in `[0, q)` with length `depth`, which is the position `pos` in base `q` from the most significant digit.\
+    /// Input : `k` - a slice of blocks with length `(q - 1) * depth`, the values of k are chosen via OT from the sums.\
+    /// For the h-th level, `k[h * (q - 1)..(h + 1) * (q - 1)]` are `sums[h * q + j]` for all `j != alpha[h]` in increasing order.\
+    /// Output : `tree` - the q-ary ggm tree, except `tree[pos] == Block::ZERO`.
+    pub fn reconstruct(&self, alpha: &[usize], k: &[Block], tree: &mut [Block]) {
+        let q = self.q;
+        assert_eq!(alpha.len(), self.depth);
+        assert_eq!(k.len(), (q - 1) * self.depth);
+        assert_eq!(tree.len(), q.pow(self.depth as u32));
+
+        let mut buf = vec![Block::ZERO; AES_BATCH.min(tree.len())];
+        let mut sums = vec![Block::ZERO; q];
+        let mut pos = 0;
+        let mut n = 1;
+        tree[0] = Block::ZERO;
+        for (&a, k) in alpha.iter().zip(k.chunks_exact(q - 1)) {
+            assert!(a < q);
+            // The children of the unknown node are garbage, and are fixed with `k`.
+            self.expand_level(tree, n, &mut buf);
+            n *= q;
+            sums.fill(Block::ZERO);
+            for (i, children) in tree[..n].chunks_exact(q).enumerate() {
+                if i != pos {
+                    for (s, c) in sums.iter_mut().zip(children) {
+                        *s ^= *c;
+                    }
+                }
+            }
+
+            let children = &mut tree[q * pos..q * pos + q];
+            let ks = k[..a]
+                .iter()
+                .chain(std::iter::once(&Block::ZERO))
+                .chain(k[a..].iter());
+            for (j, ((c, s), k)) in children.iter_mut().zip(sums.iter()).zip(ks).enumerate() {
+                *c = if j == a { Block::ZERO } else { *s ^ *k };
+            }
+            pos = q * pos + a;
+        }
+    }
+}
+
 #[test]
 fn ggm_test() {
     use crate::ggm_tree::GgmTree;
@@ -124,9 +529,9 @@ fn ggm_test() {
     let alpha = [false, true, false];
     let mut pos = 0;
 
-    for i in 0..alpha.len() {
-        pos = pos << 1;
-        if !alpha[i] {
+    for a in alpha.iter() {
+        pos <<= 1;
+        if !a {
             pos += 1;
         }
     }
@@ -150,3 +555,168 @@ fn ggm_test() {
     tree_reconstruct[pos] = tree[pos];
     assert_eq!(tree, tree_reconstruct);
 }
+
+#[test]
+fn ggm_many_test() {
+    for (depth, t) in [(2, 3), (2, 4), (5, 9), (8, 4)] {
+        let ggm = GgmTree::new(depth);
+        let seeds: Vec<Block> = (0..t).map(|_| rand::random::<Block>()).collect();
+        let mut trees = vec![Block::ZERO; t << depth];
+        let mut k0 = vec![Block::ZERO; t * depth];
+        let mut k1 = vec![Block::ZERO; t * depth];
+        ggm.gen_many(&seeds, &mut trees, &mut k0, &mut k1);
+
+        let mut expected = vec![Block::ZERO; t << depth];
+        let mut e0 = vec![Block::ZERO; t * depth];
+        let mut e1 = vec![Block::ZERO; t * depth];
+        for i in 0..t {
+            ggm.gen(
+                seeds[i],
+                &mut expected[i << depth..(i + 1) << depth],
+                &mut e0[i * depth..(i + 1) * depth],
+                &mut e1[i * depth..(i + 1) * depth],
+            );
+        }
+        assert_eq!(trees, expected);
+        assert_eq!(k0, e0);
+        assert_eq!(k1, e1);
+
+        let alphas: Vec<bool> = (0..t * depth).map(|_| rand::random::<bool>()).collect();
+        let ks: Vec<Block> = (0..t * depth)
+            .map(|i| if alphas[i] { k1[i] } else { k0[i] })
+            .collect();
+        let mut rtrees = vec![Block::ZERO; t << depth];
+        ggm.reconstruct_many(&alphas, &ks, &mut rtrees);
+        for i in 0..t {
+            let mut tree = vec![Block::ZERO; 1 << depth];
+            let alpha = &alphas[i * depth..(i + 1) * depth];
+            ggm.reconstruct(alpha, &ks[i * depth..(i + 1) * depth], &mut tree);
+            assert_eq!(rtrees[i << depth..(i + 1) << depth], tree);
+
+            let pos = alpha.iter().fold(0, |pos, &a| (pos << 1) | !a as usize);
+            assert_eq!(tree[pos], Block::ZERO);
+            tree[pos] = expected[(i << depth) + pos];
+            assert_eq!(tree, expected[i << depth..(i + 1) << depth]);
+        }
+    }
+}
+
+#[test]
+fn ggm_pruned_test() {
+    use crate::aes::Aes;
+
+    let keys = [Aes::new(Block::ZERO), Aes::new(Block::from(1u128))];
+    for n in [2, 3, 5, 6, 7, 12, 13, 600, 1025] {
+        let ggm = GgmTree::with_leaves(n);
+        let depth = ggm.depth();
+        assert_eq!(1 << depth, n.next_power_of_two());
+
+        // Expand the full tree level by level, and keep the ancestors of the first `n` leaves.
+        let seed = rand::random::<Block>();
+        let mut level = vec![seed];
+        let mut e0 = vec![Block::ZERO; depth];
+        let mut e1 = vec![Block::ZERO; depth];
+        for h in 0..depth {
+            level = level
+                .iter()
+                .flat_map(|&p| keys.map(|aes| aes.encrypt_block(p) ^ p))
+                .collect();
+            let m = (n - 1) / (1 << (depth - h - 1)) + 1;
+            for (i, x) in level[..m].iter().enumerate() {
+                if i % 2 == 0 {
+                    e0[h] ^= *x;
+                } else {
+                    e1[h] ^= *x;
+                }
+            }
+        }
+
+        let mut tree = vec![Block::ZERO; n];
+        let mut k0 = vec![Block::ZERO; depth];
+        let mut k1 = vec![Block::ZERO; depth];
+        ggm.gen(seed, &mut tree, &mut k0, &mut k1);
+        assert_eq!(tree, level[..n]);
+        assert_eq!(k0, e0);
+        assert_eq!(k1, e1);
+
+        for pos in (0..n).step_by(n / 8 + 1).chain([n - 1]) {
+            let alpha: Vec<bool> = (0..depth)
+                .map(|h| (pos >> (depth - 1 - h)) & 1 == 0)
+                .collect();
+            let k: Vec<Block> = (0..depth)
+                .map(|h| if alpha[h] { k1[h] } else { k0[h] })
+                .collect();
+            let mut tree_reconstruct = vec![Block::ZERO; n];
+            ggm.reconstruct(&alpha, &k, &mut tree_reconstruct);
+            assert_eq!(tree_reconstruct[pos], Block::ZERO);
+            tree_reconstruct[pos] = tree[pos];
+            assert_eq!(tree_reconstruct, tree);
+        }
+
+        let seeds: Vec<Block> = (0..5).map(|_| rand::random::<Block>()).collect();
+        let mut trees = vec![Block::ZERO; 5 * n];
+        let mut k0 = vec![Block::ZERO; 5 * depth];
+        let mut k1 = vec![Block::ZERO; 5 * depth];
+        ggm.gen_many(&seeds, &mut trees, &mut k0, &mut k1);
+        ggm.gen(seeds[4], &mut tree, &mut e0, &mut e1);
+        assert_eq!(trees[4 * n..], tree);
+        assert_eq!(k0[4 * depth..], e0);
+        assert_eq!(k1[4 * depth..], e1);
+    }
+
+    // A full tree of depth 1 is handled as well.
+    let ggm = GgmTree::new(1);
+    let mut tree = [Block::ZERO; 2];
+    let (mut k0, mut k1) = ([Block::ZERO], [Block::ZERO]);
+    ggm.gen(Block::ONES, &mut tree, &mut k0, &mut k1);
+    assert_eq!((k0[0], k1[0]), (tree[0], tree[1]));
+}
+
+#[test]
+fn qary_ggm_test() {
+    use crate::Block;
+
+    let q: usize = 4;
+    let depth = 3;
+    let mut tree = vec![Block::ZERO; q.pow(depth as u32)];
+    let mut sums = vec![Block::ZERO; q * depth];
+    let mut k = vec![Block::ZERO; (q - 1) * depth];
+    let alpha = [1, 3, 0];
+    let mut pos = 0;
+
+    for a in alpha.iter() {
+        pos = pos * q + a;
+    }
+
+    let ggm = QaryGgmTree::new(q, depth);
+
+    ggm.gen(rand::random::<Block>(), &mut tree, &mut sums);
+
+    for h in 0..depth {
+        let mut ks = sums[h * q..(h + 1) * q].to_vec();
+        ks.remove(alpha[h]);
+        k[h * (q - 1)..(h + 1) * (q - 1)].copy_from_slice(&ks);
+    }
+
+    let mut tree_reconstruct = vec![Block::ZERO; q.pow(depth as u32)];
+    ggm.reconstruct(&alpha, &k, &mut tree_reconstruct);
+
+    assert_eq!(tree_reconstruct[pos], Block::ZERO);
+    tree_reconstruct[pos] = tree[pos];
+    assert_eq!(tree, tree_reconstruct);
+
+    // The binary case is the same as `GgmTree`.
+    let depth = 11;
+    let seed = rand::random::<Block>();
+    let mut tree = vec![Block::ZERO; 1 << depth];
+    let mut k0 = vec![Block::ZERO; depth];
+    let mut k1 = vec![Block::ZERO; depth];
+    GgmTree::new(depth).gen(seed, &mut tree, &mut k0, &mut k1);
+    let mut qary_tree = vec![Block::ZERO; 1 << depth];
+    let mut sums = vec![Block::ZERO; 2 * depth];
+    QaryGgmTree::new(2, depth).gen(seed, &mut qary_tree, &mut sums);
+    assert_eq!(tree, qary_tree);
+    for h in 0..depth {
+        assert_eq!([k0[h], k1[h]], sums[2 * h..2 * h + 2]);
+    }
+}
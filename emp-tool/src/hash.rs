@@ -1,8 +1,12 @@
 //! Define hashes based on AES.
 
+use rayon::prelude::*;
 use sha2::{Digest, Sha256};
 
-use crate::{aes::Aes, Block};
+use crate::{
+    aes::{Aes, AES_BATCH, PAR_CHUNK},
+    Block,
+};
 
 /// Correlation-robust hash function for 128-bit inputs
 /// (cf. <https://eprint.iacr.org/2019/074>, §7.2).
@@ -38,6 +42,47 @@ impl CrHash {
         }
         res
     }
+
+    /// Hash block slice in place.
+    #[inline(always)]
+    pub fn hash_block_slice(&self, blks: &mut [Block]) {
+        let mut buf = [Block::ZERO; AES_BATCH];
+        for chunk in blks.chunks_mut(AES_BATCH) {
+            let buf = &mut buf[..chunk.len()];
+            buf.copy_from_slice(chunk);
+            self.0.encrypt_block_slice(buf);
+            for (x, y) in chunk.iter_mut().zip(buf.iter()) {
+                *x ^= *y;
+            }
+        }
+    }
+
+    /// Hash block slice `blks` into `out`.
+    #[inline(always)]
+    pub fn hash_block_slice_into(&self, out: &mut [Block], blks: &[Block]) {
+        assert_eq!(out.len(), blks.len());
+        out.copy_from_slice(blks);
+        self.0.encrypt_block_slice(out);
+        for (x, y) in out.iter_mut().zip(blks.iter()) {
+            *x ^= *y;
+        }
+    }
+
+    /// Hash block slice in place with multiple threads.
+    #[inline(always)]
+    pub fn par_hash_block_slice(&self, blks: &mut [Block]) {
+        blks.par_chunks_mut(PAR_CHUNK)
+            .for_each(|chunk| self.hash_block_slice(chunk));
+    }
+
+    /// Hash block slice `blks` into `out` with multiple threads.
+    #[inline(always)]
+    pub fn par_hash_block_slice_into(&self, out: &mut [Block], blks: &[Block]) {
+        assert_eq!(out.len(), blks.len());
+        out.par_chunks_mut(PAR_CHUNK)
+            .zip(blks.par_chunks(PAR_CHUNK))
+            .for_each(|(out, blks)| self.hash_block_slice_into(out, blks));
+    }
 }
 
 impl Default for CrHash {
@@ -83,10 +128,56 @@ impl CcrHash {
         }
         let mut res = self.0.encrypt_many_blocks::<N>(t);
         for i in 0..N {
-            res[i] ^= blks[i]
+            res[i] ^= t[i]
         }
         res
     }
+
+    /// Hash block slice in place.
+    #[inline(always)]
+    pub fn hash_block_slice(&self, blks: &mut [Block]) {
+        let mut buf = [Block::ZERO; AES_BATCH];
+        for chunk in blks.chunks_mut(AES_BATCH) {
+            let buf = &mut buf[..chunk.len()];
+            for (x, y) in chunk.iter_mut().zip(buf.iter_mut()) {
+                *x = Block::sigma(*x);
+                *y = *x;
+            }
+            self.0.encrypt_block_slice(buf);
+            for (x, y) in chunk.iter_mut().zip(buf.iter()) {
+                *x ^= *y;
+            }
+        }
+    }
+
+    /// Hash block slice `blks` into `out`.
+    #[inline(always)]
+    pub fn hash_block_slice_into(&self, out: &mut [Block], blks: &[Block]) {
+        assert_eq!(out.len(), blks.len());
+        for (x, y) in out.iter_mut().zip(blks.iter()) {
+            *x = Block::sigma(*y);
+        }
+        self.0.encrypt_block_slice(out);
+        for (x, y) in out.iter_mut().zip(blks.iter()) {
+            *x ^= Block::sigma(*y);
+        }
+    }
+
+    /// Hash block slice in place with multiple threads.
+    #[inline(always)]
+    pub fn par_hash_block_slice(&self, blks: &mut [Block]) {
+        blks.par_chunks_mut(PAR_CHUNK)
+            .for_each(|chunk| self.hash_block_slice(chunk));
+    }
+
+    /// Hash block slice `blks` into `out` with multiple threads.
+    #[inline(always)]
+    pub fn par_hash_block_slice_into(&self, out: &mut [Block], blks: &[Block]) {
+        assert_eq!(out.len(), blks.len());
+        out.par_chunks_mut(PAR_CHUNK)
+            .zip(blks.par_chunks(PAR_CHUNK))
+            .for_each(|(out, blks)| self.hash_block_slice_into(out, blks));
+    }
 }
 
 impl Default for CcrHash {
@@ -136,6 +227,55 @@ impl TccrHash {
         }
         res
     }
+
+    /// Hash block slice in place, where `blks[i]` is hashed with tweak `id + i` (wrapping).
+    #[inline(always)]
+    pub fn hash_block_slice(&self, blks: &mut [Block], id: u64) {
+        let mut buf = [Block::ZERO; AES_BATCH];
+        for (c, chunk) in blks.chunks_mut(AES_BATCH).enumerate() {
+            let buf = &mut buf[..chunk.len()];
+            buf.copy_from_slice(chunk);
+            self.0.encrypt_block_slice(buf);
+            let id = id.wrapping_add((c * AES_BATCH) as u64);
+            for (i, (x, y)) in chunk.iter_mut().zip(buf.iter()).enumerate() {
+                *x = *y ^ Block::from([id.wrapping_add(i as u64), 0u64]);
+            }
+            self.0.encrypt_block_slice(chunk);
+            for (x, y) in chunk.iter_mut().zip(buf.iter()) {
+                *x ^= *y;
+            }
+        }
+    }
+
+    /// Hash block slice `blks` into `out`, where `blks[i]` is hashed with tweak `id + i` (wrapping).
+    #[inline(always)]
+    pub fn hash_block_slice_into(&self, out: &mut [Block], blks: &[Block], id: u64) {
+        assert_eq!(out.len(), blks.len());
+        out.copy_from_slice(blks);
+        self.hash_block_slice(out, id);
+    }
+
+    /// Hash block slice in place with multiple threads, where `blks[i]` is hashed with tweak `id + i` (wrapping).
+    #[inline(always)]
+    pub fn par_hash_block_slice(&self, blks: &mut [Block], id: u64) {
+        blks.par_chunks_mut(PAR_CHUNK)
+            .enumerate()
+            .for_each(|(c, chunk)| {
+                self.hash_block_slice(chunk, id.wrapping_add((c * PAR_CHUNK) as u64))
+            });
+    }
+
+    /// Hash block slice `blks` into `out` with multiple threads, where `blks[i]` is hashed with tweak `id + i` (wrapping).
+    #[inline(always)]
+    pub fn par_hash_block_slice_into(&self, out: &mut [Block], blks: &[Block], id: u64) {
+        assert_eq!(out.len(), blks.len());
+        out.par_chunks_mut(PAR_CHUNK)
+            .zip(blks.par_chunks(PAR_CHUNK))
+            .enumerate()
+            .for_each(|(c, (out, blks))| {
+                self.hash_block_slice_into(out, blks, id.wrapping_add((c * PAR_CHUNK) as u64))
+            });
+    }
 }
 
 impl Default for TccrHash {
@@ -145,7 +285,102 @@ impl Default for TccrHash {
     }
 }
 
+/// Multi-instance tweakable circular correlation-robust hash function
+/// (cf. <https://eprint.iacr.org/2019/1168>, §4).
+///
+/// The function computes `π_{s ⊕ i}(x) ⊕ x`, where `s` is a random seed and `i` is a tweak.
+/// Keys `s ⊕ i` are derived in batches of `BATCH` consecutive tweaks, and their key schedules
+/// are computed in parallel. Each call of `hash` consumes `K` keys of the current batch.
+pub struct MitCcrHash<const BATCH: usize> {
+    keys: [Aes; BATCH],
+    key_used: usize,
+    start_point: Block,
+    gid: u64,
+}
+
+impl<const BATCH: usize> MitCcrHash<BATCH> {
+    /// New a function with seed `s`, starting from tweak `0`.
+    #[inline(always)]
+    pub fn new(seed: Block) -> Self {
+        // The keys are placeholders until the first `hash`, since `key_used == BATCH` renews them.
+        // The array repeat computes one key schedule and copies it, which is negligible next to the renewal.
+        Self {
+            keys: [Aes::new(Block::ZERO); BATCH],
+            key_used: BATCH,
+            start_point: seed,
+            gid: 0,
+        }
+    }
+
+    /// Set the seed `s`. The keys are renewed at the next call of `hash`.
+    #[inline(always)]
+    pub fn set_s(&mut self, seed: Block) {
+        self.start_point = seed;
+        self.key_used = BATCH;
+    }
+
+    /// Get the next tweak to be used in key derivation.
+    #[inline(always)]
+    pub fn gid(&self) -> u64 {
+        self.gid
+    }
+
+    /// Renew the keys with the tweaks starting from `gid`.
+    #[inline(always)]
+    pub fn renew_ks_with_gid(&mut self, gid: u64) {
+        self.gid = gid;
+        self.renew_ks();
+    }
+
+    /// Renew the keys with the next `BATCH` tweaks.
+    #[inline(always)]
+    pub fn renew_ks(&mut self) {
+        let mut keys = [Block::ZERO; BATCH];
+        for key in keys.iter_mut() {
+            *key = self.start_point ^ Block::from([0u64, self.gid]);
+            self.gid += 1;
+        }
+        self.keys = Aes::para_new(keys);
+        self.key_used = 0;
+    }
+
+    /// Hash `K * H` blocks, where each batch of `H` blocks uses the same key.\
+    /// The keys are renewed if fewer than `K` keys of the current batch are left, and the left keys are skipped.\
+    /// Only handle the first `K * H` blocks of blks, do not handle the rest.
+    #[inline(always)]
+    pub fn hash<const K: usize, const H: usize>(&mut self, blks: &mut [Block]) {
+        assert!(K <= BATCH && BATCH.is_multiple_of(K));
+        assert!(blks.len() >= K * H);
+        if self.key_used + K > BATCH {
+            self.renew_ks();
+        }
+
+        let keys: [Aes; K] = self.keys[self.key_used..self.key_used + K]
+            .try_into()
+            .unwrap();
+        self.key_used += K;
+
+        let mut tmp = [[Block::ZERO; H]; K];
+        let tmp = tmp.as_flattened_mut();
+        tmp.copy_from_slice(&blks[..K * H]);
+        Aes::para_encrypt::<K, H>(keys, tmp);
+        for (x, y) in blks.iter_mut().zip(tmp.iter()) {
+            *x ^= *y;
+        }
+    }
+
+    /// Hash `K * H` blocks with `sigma` applied first, i.e., `π_{s ⊕ i}(σ(x)) ⊕ σ(x)`.
+    #[inline(always)]
+    pub fn hash_cir<const K: usize, const H: usize>(&mut self, blks: &mut [Block]) {
+        for x in blks[..K * H].iter_mut() {
+            *x = Block::sigma(*x);
+        }
+        self.hash::<K, H>(blks);
+    }
+}
+
 /// A wrapper of SHA256
+#[derive(Clone)]
 pub struct Hash(Sha256);
 
 impl Hash {
@@ -227,3 +462,153 @@ fn hash_test() {
         Block::from(0x68e0f8bae7d74f1581fc3d4b682d6260)
     );
 }
+
+#[test]
+fn hash_slice_test() {
+    let n = PAR_CHUNK * 2 + AES_BATCH + 3;
+    let blks: Vec<Block> = (0..n).map(|_| rand::random::<Block>()).collect();
+    let key = rand::random::<Block>();
+
+    let h = CrHash::new_with_key(key);
+    let expected: Vec<Block> = blks.iter().map(|x| h.hash_block(*x)).collect();
+    assert_eq!(
+        h.hash_many_blocks::<3>(blks[..3].try_into().unwrap()),
+        expected[..3]
+    );
+    let mut x = blks.clone();
+    h.hash_block_slice(&mut x);
+    assert_eq!(x, expected);
+    let mut x = blks.clone();
+    h.par_hash_block_slice(&mut x);
+    assert_eq!(x, expected);
+    let mut x = vec![Block::ZERO; n];
+    h.hash_block_slice_into(&mut x, &blks);
+    assert_eq!(x, expected);
+    let mut x = vec![Block::ZERO; n];
+    h.par_hash_block_slice_into(&mut x, &blks);
+    assert_eq!(x, expected);
+
+    let h = CcrHash::new_with_key(key);
+    let expected: Vec<Block> = blks.iter().map(|x| h.hash_block(*x)).collect();
+    assert_eq!(
+        h.hash_many_blocks::<3>(blks[..3].try_into().unwrap()),
+        expected[..3]
+    );
+    let mut x = blks.clone();
+    h.hash_block_slice(&mut x);
+    assert_eq!(x, expected);
+    let mut x = blks.clone();
+    h.par_hash_block_slice(&mut x);
+    assert_eq!(x, expected);
+    let mut x = vec![Block::ZERO; n];
+    h.hash_block_slice_into(&mut x, &blks);
+    assert_eq!(x, expected);
+    let mut x = vec![Block::ZERO; n];
+    h.par_hash_block_slice_into(&mut x, &blks);
+    assert_eq!(x, expected);
+
+    let h = TccrHash::new_with_key(key);
+    // The tweaks wrap around in the last case.
+    for id in [
+        rand::random::<u32>() as u64,
+        u64::MAX - PAR_CHUNK as u64 - 1,
+    ] {
+        let expected: Vec<Block> = blks
+            .iter()
+            .enumerate()
+            .map(|(i, x)| h.hash_block(*x, id.wrapping_add(i as u64)))
+            .collect();
+        assert_eq!(
+            h.hash_many_blocks::<3>(
+                blks[..3].try_into().unwrap(),
+                [id, id.wrapping_add(1), id.wrapping_add(2)]
+            ),
+            expected[..3]
+        );
+        let mut x = blks.clone();
+        h.hash_block_slice(&mut x, id);
+        assert_eq!(x, expected);
+        let mut x = blks.clone();
+        h.par_hash_block_slice(&mut x, id);
+        assert_eq!(x, expected);
+        let mut x = vec![Block::ZERO; n];
+        h.hash_block_slice_into(&mut x, &blks, id);
+        assert_eq!(x, expected);
+        let mut x = vec![Block::ZERO; n];
+        h.par_hash_block_slice_into(&mut x, &blks, id);
+        assert_eq!(x, expected);
+    }
+}
+
+#[test]
+fn mitccrh_test() {
+    let seed = rand::random::<Block>();
+    let mut h = MitCcrHash::<8>::new(seed);
+
+    let blks = rand::random::<[Block; 12]>();
+    let mut x = blks;
+    h.hash::<4, 3>(&mut x);
+    for i in 0..4 {
+        let aes = Aes::new(seed ^ Block::from([0u64, i as u64]));
+        for j in 0..3 {
+            let y = blks[i * 3 + j];
+            assert_eq!(x[i * 3 + j], aes.encrypt_block(y) ^ y);
+        }
+    }
+
+    let mut x = blks;
+    h.hash_cir::<4, 3>(&mut x);
+    for i in 0..4 {
+        let aes = Aes::new(seed ^ Block::from([0u64, (i + 4) as u64]));
+        for j in 0..3 {
+            let y = Block::sigma(blks[i * 3 + j]);
+            assert_eq!(x[i * 3 + j], aes.encrypt_block(y) ^ y);
+        }
+    }
+    assert_eq!(h.gid(), 8);
+
+    let mut x = blks;
+    h.hash::<2, 1>(&mut x);
+    assert_eq!(h.gid(), 16);
+    let aes = Aes::new(seed ^ Block::from([0u64, 8]));
+    assert_eq!(x[0], aes.encrypt_block(blks[0]) ^ blks[0]);
+    assert_eq!(x[2..], blks[2..]);
+
+    h.renew_ks_with_gid(100);
+    let mut x = blks;
+    h.hash::<1, 12>(&mut x);
+    let aes = Aes::new(seed ^ Block::from([0u64, 100]));
+    for (x, y) in x.iter().zip(blks) {
+        assert_eq!(*x, aes.encrypt_block(y) ^ y);
+    }
+
+    // The keys are renewed if the batch has fewer keys left than `K`.
+    let mut h = MitCcrHash::<8>::new(seed);
+    for i in 0..3 {
+        let mut x = blks;
+        h.hash::<2, 1>(&mut x);
+        for j in 0..2 {
+            let aes = Aes::new(seed ^ Block::from([0u64, (2 * i + j) as u64]));
+            assert_eq!(x[j], aes.encrypt_block(blks[j]) ^ blks[j]);
+        }
+    }
+    let mut x = blks;
+    h.hash::<4, 1>(&mut x);
+    assert_eq!(h.gid(), 16);
+    for j in 0..4 {
+        let aes = Aes::new(seed ^ Block::from([0u64, (8 + j) as u64]));
+        assert_eq!(x[j], aes.encrypt_block(blks[j]) ^ blks[j]);
+    }
+}
+
+#[test]
+fn ccrh_many_blocks_test() {
+    // `hash_many_blocks` used to mask with `x` instead of `sigma(x)`.
+    let h = CcrHash::new();
+    let blks = [Block::ONES, Block::from(1u128), Block::from([0, 1])];
+    let res = h.hash_many_blocks(blks);
+    assert_eq!(res[0], Block::from(0x9e10c525db2c0ea50a1fa067183cf807));
+    for (x, y) in res.iter().zip(blks) {
+        assert_eq!(*x, h.hash_block(y));
+    }
+}
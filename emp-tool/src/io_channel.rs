@@ -1,5 +1,7 @@
 //! Define the trait for IO Channel, especially for network IO.
+mod local_io_channel;
 mod net_io_channel;
+pub use local_io_channel::LocalIO;
 pub use net_io_channel::NetIO;
 
 use crate::{
@@ -0,0 +1,117 @@
+use crate::io_channel::IOChannel;
+use std::io::{Error, ErrorKind, Result};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// An in-memory channel between two threads of the same process.\
+/// This LocalIO struct implements the IOChannel trait, and is mainly used for tests.
+pub struct LocalIO {
+    /// The sender of messages to the other end.
+    sender: Sender<Vec<u8>>,
+
+    /// The receiver of messages from the other end.
+    receiver: Receiver<Vec<u8>>,
+
+    /// The received message that is not consumed yet.
+    buffer: Vec<u8>,
+
+    /// The position of the first unconsumed byte in `buffer`.
+    pos: usize,
+
+    /// A counter that records the size of communication in Bytes.
+    comm_cnt: usize,
+}
+
+impl LocalIO {
+    /// New a pair of connected LocalIO.
+    pub fn new_pair() -> (Self, Self) {
+        let (sender0, receiver1) = channel();
+        let (sender1, receiver0) = channel();
+        (
+            Self::from_channel(sender0, receiver0),
+            Self::from_channel(sender1, receiver1),
+        )
+    }
+
+    fn from_channel(sender: Sender<Vec<u8>>, receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            sender,
+            receiver,
+            buffer: Vec::new(),
+            pos: 0,
+            comm_cnt: 0,
+        }
+    }
+
+    /// Get the size of communication in Bytes.
+    pub fn comm_cnt(&self) -> usize {
+        self.comm_cnt
+    }
+}
+
+impl IOChannel for LocalIO {
+    #[inline(always)]
+    fn send_bytes(&mut self, buffer: &[u8]) -> Result<()> {
+        self.comm_cnt += buffer.len();
+        self.sender
+            .send(buffer.to_vec())
+            .map_err(|_| Error::new(ErrorKind::BrokenPipe, "The other end is closed"))
+    }
+
+    #[inline(always)]
+    fn recv_bytes(&mut self, buffer: &mut [u8]) -> Result<()> {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            if self.pos == self.buffer.len() {
+                self.buffer = self
+                    .receiver
+                    .recv()
+                    .map_err(|_| Error::new(ErrorKind::UnexpectedEof, "The other end is closed"))?;
+                self.pos = 0;
+            }
+            let len = (buffer.len() - filled).min(self.buffer.len() - self.pos);
+            buffer[filled..filled + len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+            filled += len;
+            self.pos += len;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn local_io_test() {
+    use crate::block::Block;
+
+    const NUM: usize = 10;
+    let send_bytes = rand::random::<[u8; NUM]>();
+    let send_block_vec = rand::random::<[Block; NUM]>();
+    let (mut io0, mut io1) = LocalIO::new_pair();
+
+    let handle = std::thread::spawn(move || {
+        io0.send_bytes(&send_bytes).unwrap();
+        io0.send_block_vec(&send_block_vec).unwrap();
+        io0.recv_block().unwrap()
+    });
+
+    // Receive with a different granularity than sent.
+    let mut recv_bytes = vec![0u8; NUM + 3];
+    io1.recv_bytes(&mut recv_bytes).unwrap();
+    let recv_block_vec = io1.recv_block_vec(NUM - 1).unwrap();
+    io1.send_block(&Block::ONES).unwrap();
+
+    assert_eq!(send_bytes[..], recv_bytes[..NUM]);
+    assert_eq!(
+        bytemuck::cast_slice::<Block, u8>(&send_block_vec)[..3],
+        recv_bytes[NUM..]
+    );
+    assert_eq!(
+        bytemuck::cast_slice::<Block, u8>(&send_block_vec)[3..3 + 16 * (NUM - 1)],
+        *bytemuck::cast_slice::<Block, u8>(&recv_block_vec)
+    );
+    assert_eq!(handle.join().unwrap(), Block::ONES);
+    assert_eq!(io1.comm_cnt(), 16);
+}
@@ -129,9 +129,9 @@ fn io_test() {
 
         io.send_bytes(&send_bytes).unwrap();
         io.send_bool(&send_bool).unwrap();
-        io.send_bool_vec(&send_bool_vec.to_vec()).unwrap();
+        io.send_bool_vec(&send_bool_vec).unwrap();
         io.send_block(&send_block).unwrap();
-        io.send_block_vec(&send_block_vec.to_vec()).unwrap();
+        io.send_block_vec(&send_block_vec).unwrap();
     });
 
     {
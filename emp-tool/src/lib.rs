@@ -5,20 +5,37 @@
 pub mod aes;
 
 pub mod block;
+pub mod coin_toss;
+pub mod commit;
 pub mod constants;
+pub mod cpu_features;
+pub mod dcf;
+pub mod dpf;
+pub mod emp_compat;
 pub mod ggm_tree;
 pub mod hash;
 pub mod io_channel;
 pub mod lpn;
+pub mod pprf;
 pub mod prg;
 pub mod prp;
+pub mod random_oracle;
 pub mod sse2neon;
 pub mod tkprp;
+pub mod tprp;
+pub mod transcript;
+pub mod universal_hash;
 pub mod utils;
 
-pub use aes::Aes;
+pub use aes::{Aes, Aes256, AesDec};
 pub use block::Block;
 pub use constants::{ALICE, BOB, PUBLIC};
-pub use hash::{CcrHash, CrHash, TccrHash};
-pub use io_channel::{CommandLineOpt, IOChannel, NetIO};
+pub use dcf::{Dcf, DcfKey};
+pub use dpf::{Dpf, DpfKey};
+pub use hash::{CcrHash, CrHash, MitCcrHash, TccrHash};
+pub use io_channel::{CommandLineOpt, IOChannel, LocalIO, NetIO};
+pub use pprf::{PuncturablePrf, PuncturedKey};
+pub use random_oracle::RandomOracle;
+pub use transcript::{Transcript, TranscriptIO};
+pub use universal_hash::UniversalHash;
 pub use utils::{pack_bits_to_bytes, unpack_bytes_to_bits};
@@ -47,7 +47,7 @@ impl<const D: usize> Lpn<D> {
 
         for (i, y) in y[pos..].iter_mut().enumerate().take(4) {
             for ind in index[i * D..(i + 1) * D].iter_mut() {
-                *ind &= self.mask;
+                *ind = u32::from_le(*ind) & self.mask;
                 *ind = if *ind >= self.k { *ind - self.k } else { *ind };
 
                 *y ^= x[*ind as usize];
@@ -70,7 +70,7 @@ impl<const D: usize> Lpn<D> {
 
         for (i, y) in y.iter_mut().enumerate().take(4) {
             for ind in index[i * D..(i + 1) * D].iter_mut() {
-                *ind &= self.mask;
+                *ind = u32::from_le(*ind) & self.mask;
                 *ind = if *ind >= self.k { *ind - self.k } else { *ind };
 
                 *y ^= x[*ind as usize];
@@ -81,7 +81,7 @@ impl<const D: usize> Lpn<D> {
     // Compute one row.
     #[inline]
     fn compute_one_row(&self, y: &mut [Block], x: &[Block], pos: usize, prp: &Prp) {
-        let block_size = (D + 4 - 1) / 4;
+        let block_size = D.div_ceil(4);
         let mut index = (0..block_size)
             .map(|i| Block::from([pos as u64, i as u64]))
             .collect::<Vec<Block>>();
@@ -89,7 +89,7 @@ impl<const D: usize> Lpn<D> {
         let index = bytemuck::cast_slice_mut::<_, u32>(&mut index);
 
         for ind in index.iter_mut().take(D) {
-            *ind &= self.mask;
+            *ind = u32::from_le(*ind) & self.mask;
             *ind = if *ind >= self.k { *ind - self.k } else { *ind };
             y[pos] ^= x[*ind as usize];
         }
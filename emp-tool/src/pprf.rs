@@ -0,0 +1,107 @@
+//! Implement a puncturable PRF from the GGM tree.
+
+use crate::{ggm_tree::GgmTree, Block};
+
+/// A puncturable PRF with the domain `[0, n)`, where the output on `x` is the `x`-th leaf of the GGM tree from the key.\
+/// The outputs are the same as the leaves generated by `GgmTree::with_leaves(n)`.
+pub struct PuncturablePrf(GgmTree);
+
+/// A key punctured at a point `x`, which evaluates the PRF on all points except `x`.\
+/// `co_path[h]` is the sibling of the node on the path to `x` in the `(h+1)`-th level of the tree.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PuncturedKey {
+    /// The punctured point.
+    pub x: usize,
+
+    /// The nodes on the co-path of `x`, with size `depth`.
+    pub co_path: Vec<Block>,
+}
+
+impl PuncturablePrf {
+    /// New a puncturable PRF with the domain size `n >= 2`.
+    #[inline(always)]
+    pub fn new(n: usize) -> Self {
+        Self(GgmTree::with_leaves(n))
+    }
+
+    /// Get the domain size.
+    #[inline(always)]
+    pub fn domain_size(&self) -> usize {
+        self.0.leaves()
+    }
+
+    /// Get the depth of the tree, i.e., the size of the co-path of a punctured key.
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.0.depth()
+    }
+
+    /// Evaluate the PRF on `x`.
+    #[inline(always)]
+    pub fn eval(&self, key: Block, x: usize) -> Block {
+        assert!(x < self.domain_size());
+        self.0.eval_path(key, 0, x)
+    }
+
+    /// Evaluate the PRF on all points of the domain.
+    pub fn eval_all(&self, key: Block) -> Vec<Block> {
+        let mut res = vec![Block::ZERO; self.domain_size()];
+        let mut k0 = vec![Block::ZERO; self.depth()];
+        let mut k1 = vec![Block::ZERO; self.depth()];
+        self.0.gen(key, &mut res, &mut k0, &mut k1);
+        res
+    }
+
+    /// Puncture the key at `x`.
+    #[inline(always)]
+    pub fn puncture(&self, key: Block, x: usize) -> PuncturedKey {
+        assert!(x < self.domain_size());
+        PuncturedKey {
+            x,
+            co_path: self.0.co_path(key, x),
+        }
+    }
+
+    /// Evaluate the PRF on `y` with a punctured key, which is `None` if `y` is the punctured point.
+    pub fn eval_punctured(&self, key: &PuncturedKey, y: usize) -> Option<Block> {
+        assert!(y < self.domain_size());
+        assert_eq!(key.co_path.len(), self.depth());
+        if y == key.x {
+            return None;
+        }
+        // The paths to `x` and `y` split below the `h`-th level.
+        let h = (key.x ^ y).leading_zeros() as usize - (usize::BITS as usize - self.depth());
+        Some(self.0.eval_path(key.co_path[h], h + 1, y))
+    }
+
+    /// Evaluate the PRF on all points of the domain with a punctured key, where the output on the punctured point is `Block::ZERO`.
+    pub fn eval_all_punctured(&self, key: &PuncturedKey) -> Vec<Block> {
+        let mut res = vec![Block::ZERO; self.domain_size()];
+        self.0.expand_co_path(key.x, &key.co_path, &mut res);
+        res
+    }
+}
+
+#[test]
+fn pprf_test() {
+    for n in [2, 5, 64, 600] {
+        let pprf = PuncturablePrf::new(n);
+        let key = rand::random::<Block>();
+        let all = pprf.eval_all(key);
+        assert_eq!(all.len(), n);
+        for x in (0..n).step_by(n / 16 + 1).chain([n - 1]) {
+            assert_eq!(pprf.eval(key, x), all[x]);
+
+            let pk = pprf.puncture(key, x);
+            assert_eq!(pk.co_path.len(), pprf.depth());
+            for (y, v) in all.iter().enumerate() {
+                let expected = if y == x { None } else { Some(*v) };
+                assert_eq!(pprf.eval_punctured(&pk, y), expected);
+            }
+            let mut punctured = all.clone();
+            punctured[x] = Block::ZERO;
+            assert_eq!(pprf.eval_all_punctured(&pk), punctured);
+        }
+    }
+}
@@ -1,35 +1,69 @@
 //! Implement AES-based PRG.
 
-use crate::{aes::Aes, Block};
+use crate::{
+    aes::{Aes, PAR_CHUNK},
+    hash::Hash,
+    Block,
+};
 use rand::Rng;
 use rand_core::{
     block::{BlockRng, BlockRngCore},
     CryptoRng, RngCore, SeedableRng,
 };
+use rayon::prelude::*;
+
+/// The prefix absorbed before deriving the seed of a child PRG.
+const FORK_DOMAIN_PREFIX: &[u8] = b"emp-rust/prg/fork/v1";
+
+/// The number of `u32` values generated by `PrgCore` at once.
+const RESULTS_LEN: usize = 32;
 
 ///Struct of PRG Core
 #[derive(Clone, Copy, Debug)]
 struct PrgCore {
     aes: Aes,
+    seed: Block,
+    nonce: u64,
     state: u64,
 }
 
+impl PrgCore {
+    // Compute [AES(nonce || ctr)..AES(nonce || ctr+8)]
+    #[inline(always)]
+    fn generate_at(&self, ctr: u64) -> [Block; 8] {
+        let nonce = Block::from([0, self.nonce]);
+        let states: [Block; 8] = std::array::from_fn(
+            #[inline(always)]
+            |i| Block::from([ctr.wrapping_add(i as u64), 0]) ^ nonce,
+        );
+        self.aes.encrypt_many_blocks(states)
+    }
+
+    #[inline(always)]
+    fn from_seed_and_nonce(seed: Block, nonce: u64) -> Self {
+        let aes = Aes::new(seed);
+        Self {
+            aes,
+            seed,
+            nonce,
+            state: 0u64,
+        }
+    }
+}
+
 impl BlockRngCore for PrgCore {
     type Item = u32;
-    type Results = [u32; 32];
+    type Results = [u32; RESULTS_LEN];
 
-    // Compute [AES(state)..AES(state+8)]
+    // Compute [AES(nonce || state)..AES(nonce || state+8)]
     #[inline(always)]
     fn generate(&mut self, results: &mut Self::Results) {
-        let states = [0; 8].map(
-            #[inline(always)]
-            |_| {
-                let x = self.state;
-                self.state += 1;
-                Block::from([x, 0])
-            },
-        );
-        *results = bytemuck::cast(self.aes.encrypt_many_blocks(states))
+        *results = bytemuck::cast(self.generate_at(self.state));
+        // Read the blocks as little-endian words, as on x86.
+        for r in results.iter_mut() {
+            *r = u32::from_le(*r);
+        }
+        self.state = self.state.wrapping_add(8);
     }
 }
 
@@ -38,13 +72,31 @@ impl SeedableRng for PrgCore {
 
     #[inline(always)]
     fn from_seed(seed: Self::Seed) -> Self {
-        let aes = Aes::new(seed);
-        Self { aes, state: 0u64 }
+        Self::from_seed_and_nonce(seed, 0)
     }
 }
 
 impl CryptoRng for PrgCore {}
 
+/// The state of a `Prg`, which is exported by `Prg::export_state`.\
+/// A Prg imported from the state outputs exactly the same stream as the exporting one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrgState {
+    /// The seed.
+    pub seed: Block,
+
+    /// The nonce.
+    pub nonce: u64,
+
+    /// The counter of the first block of the buffered results.
+    pub counter: u64,
+
+    /// The number of `u32` values that are consumed in the buffered results.\
+    /// It is `32` if no result is buffered.
+    pub index: usize,
+}
+
 /// Struct of PRG
 #[derive(Clone, Debug)]
 pub struct Prg(BlockRng<PrgCore>);
@@ -95,6 +147,73 @@ impl Prg {
         Prg::from_seed(seed)
     }
 
+    /// New a Prg with `seed` and `nonce`.\
+    /// Prgs with the same seed and distinct nonces output independent streams.
+    /// `Prg::from_seed(seed)` is the same as `Prg::from_seed_and_nonce(seed, 0)`.
+    #[inline(always)]
+    pub fn from_seed_and_nonce(seed: Block, nonce: u64) -> Self {
+        Prg(BlockRng::new(PrgCore::from_seed_and_nonce(seed, nonce)))
+    }
+
+    /// Get the counter, i.e., the number of blocks of the stream that are output.\
+    /// A block that is partially output counts as one.
+    #[inline(always)]
+    pub fn counter(&self) -> u64 {
+        let remaining = (RESULTS_LEN - self.0.index()) / 4;
+        self.0.core.state.wrapping_sub(remaining as u64)
+    }
+
+    /// Set the counter, such that the next output starts from the `counter`-th block of the stream.
+    #[inline(always)]
+    pub fn set_counter(&mut self, counter: u64) {
+        self.0.core.state = counter;
+        self.0.reset();
+    }
+
+    /// Export the state, from which `import_state` reproduces the remaining output stream.\
+    /// The state contains the seed, so it must be kept as secret as the seed.
+    #[inline(always)]
+    pub fn export_state(&self) -> PrgState {
+        let index = self.0.index();
+        let counter = if index < RESULTS_LEN {
+            self.0.core.state.wrapping_sub(8)
+        } else {
+            self.0.core.state
+        };
+        PrgState {
+            seed: self.0.core.seed,
+            nonce: self.0.core.nonce,
+            counter,
+            index,
+        }
+    }
+
+    /// New a Prg from a state exported by `export_state`.
+    #[inline(always)]
+    pub fn import_state(state: &PrgState) -> Self {
+        assert!(state.index <= RESULTS_LEN);
+        let mut prg = Prg::from_seed_and_nonce(state.seed, state.nonce);
+        prg.0.core.state = state.counter;
+        if state.index < RESULTS_LEN {
+            prg.0.generate_and_set(state.index);
+        }
+        prg
+    }
+
+    /// New a child Prg, whose seed is derived from the seed and nonce of this Prg and `label`.\
+    /// The child only depends on `label` but not on the counter of this Prg, so children with distinct labels are independent of each other and of this Prg.
+    #[inline(always)]
+    pub fn fork(&self, label: &[u8]) -> Self {
+        let mut hasher = Hash::new();
+        hasher.update(FORK_DOMAIN_PREFIX);
+        hasher.update_block(self.0.core.seed);
+        hasher.update(&self.0.core.nonce.to_le_bytes());
+        hasher.update(label);
+        let mut seed = Block::ZERO;
+        seed.as_mut().copy_from_slice(&hasher.finalize()[..16]);
+        Prg::from_seed(seed)
+    }
+
     /// Generate a random bool value.
     #[inline(always)]
     pub fn random_bool(&mut self) -> bool {
@@ -131,6 +250,123 @@ impl Prg {
         let bytes: &mut [u8] = bytemuck::cast_slice_mut(buf);
         self.fill_bytes(bytes);
     }
+
+    /// Fill a block slice with random block values in parallel.\
+    /// The output and the counter afterwards are the same as `random_blocks`.
+    pub fn random_blocks_par(&mut self, buf: &mut [Block]) {
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(buf);
+
+        // Output the buffered results first, such that the rest starts from the counter.
+        let head = ((RESULTS_LEN - self.0.index()) * 4).min(bytes.len());
+        let (head, rest) = bytes.split_at_mut(head);
+        self.0.fill_bytes(head);
+
+        let len = rest.len() / 16;
+        let (body, tail) = rest.split_at_mut(len * 16);
+        let core = self.0.core;
+        body.par_chunks_mut(PAR_CHUNK * 16)
+            .enumerate()
+            .for_each(|(i, chunk)| {
+                let ctr = core.state.wrapping_add((i * PAR_CHUNK) as u64);
+                for (j, out) in chunk.chunks_mut(8 * 16).enumerate() {
+                    let blks = core.generate_at(ctr.wrapping_add(j as u64 * 8));
+                    let blks: &[u8] = bytemuck::cast_slice(&blks);
+                    out.copy_from_slice(&blks[..out.len()]);
+                }
+            });
+        self.0.core.state = core.state.wrapping_add(len as u64);
+
+        self.0.fill_bytes(tail);
+    }
+
+    /// Generate a uniformly random integer in `[0, bound)` without bias.
+    #[inline(always)]
+    pub fn random_below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0);
+        self.gen_range(0..bound)
+    }
+
+    /// Generate `len` random bits packed into bytes, in the same layout as `pack_bits_to_bytes`.\
+    /// The unused bits of the last byte are zero.
+    #[inline(always)]
+    pub fn random_packed_bits(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len.div_ceil(8)];
+        self.fill_bytes(&mut bytes);
+        if !len.is_multiple_of(8) {
+            *bytes.last_mut().unwrap() &= (1u8 << (len % 8)) - 1;
+        }
+        bytes
+    }
+
+    /// Generate a uniformly random non-zero element of GF(2^128).
+    #[inline(always)]
+    pub fn random_nonzero_block(&mut self) -> Block {
+        loop {
+            let x = self.random_block();
+            if x != Block::ZERO {
+                return x;
+            }
+        }
+    }
+
+    /// Shuffle a slice uniformly at random with the Fisher-Yates algorithm.
+    #[inline(always)]
+    pub fn shuffle<T>(&mut self, buf: &mut [T]) {
+        for i in (1..buf.len()).rev() {
+            let j = self.random_below(i as u64 + 1) as usize;
+            buf.swap(i, j);
+        }
+    }
+
+    /// Generate a uniformly random permutation of `[0, n)`.
+    #[inline(always)]
+    pub fn random_permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut perm: Vec<usize> = (0..n).collect();
+        self.shuffle(&mut perm);
+        perm
+    }
+
+    /// Generate the non-zero positions of a random regular sparse vector with length `n` and weight `t`.\
+    /// `[0, n)` is split into `t` intervals `[i * n / t, (i + 1) * n / t)`, and one position is sampled uniformly from each interval.
+    /// The positions are output in increasing order.
+    pub fn random_regular_positions(&mut self, n: usize, t: usize) -> Vec<usize> {
+        assert!(0 < t && t <= n);
+        (0..t)
+            .map(|i| {
+                let start = i * n / t;
+                let end = (i + 1) * n / t;
+                start + self.random_below((end - start) as u64) as usize
+            })
+            .collect()
+    }
+
+    /// Generate the non-zero positions of a random sparse vector with length `n` and exact weight `t`.\
+    /// The set of positions is uniform over all subsets of size `t`, and is sampled with Floyd's algorithm.
+    /// The positions are output in increasing order.
+    pub fn random_exact_weight_positions(&mut self, n: usize, t: usize) -> Vec<usize> {
+        assert!(t <= n);
+        let mut set = std::collections::HashSet::with_capacity(t);
+        for j in n - t..n {
+            let x = self.random_below(j as u64 + 1) as usize;
+            if !set.insert(x) {
+                set.insert(j);
+            }
+        }
+        let mut positions: Vec<usize> = set.into_iter().collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Fill `e` with a random regular sparse vector of weight `t` over GF(2^128), whose non-zero values are uniform.\
+    /// Return the non-zero positions, as `random_regular_positions`.
+    pub fn random_regular_noise(&mut self, e: &mut [Block], t: usize) -> Vec<usize> {
+        e.fill(Block::ZERO);
+        let positions = self.random_regular_positions(e.len(), t);
+        for &i in positions.iter() {
+            e[i] = self.random_nonzero_block();
+        }
+        positions
+    }
 }
 
 impl Default for Prg {
@@ -147,3 +383,177 @@ fn prg_test() {
     prg.random_blocks(&mut x);
     assert_ne!(x[0], x[1]);
 }
+
+#[test]
+fn prg_counter_test() {
+    let seed = rand::random::<Block>();
+    let mut prg = Prg::from_seed(seed);
+    let mut stream = vec![Block::ZERO; 100];
+    prg.random_blocks(&mut stream);
+    assert_eq!(prg.counter(), 100);
+
+    // Seek in the stream.
+    let mut prg = Prg::from_seed(seed);
+    prg.set_counter(37);
+    assert_eq!(prg.counter(), 37);
+    assert_eq!(prg.random_block(), stream[37]);
+    assert_eq!(prg.counter(), 38);
+    prg.set_counter(3);
+    assert_eq!(prg.random_block(), stream[3]);
+    prg.random_byte();
+    assert_eq!(prg.counter(), 5);
+
+    // Nonces give independent streams.
+    assert_eq!(Prg::from_seed_and_nonce(seed, 0).random_block(), stream[0]);
+    let x = Prg::from_seed_and_nonce(seed, 1).random_block();
+    assert_eq!(x, Prg::from_seed_and_nonce(seed, 1).random_block());
+    assert!(!stream.contains(&x));
+
+    // Children only depend on the seed, the nonce and the label.
+    let mut child = prg.fork(b"child");
+    assert_eq!(
+        child.random_block(),
+        Prg::from_seed(seed).fork(b"child").random_block()
+    );
+    assert_ne!(
+        Prg::from_seed(seed).fork(b"child").random_block(),
+        Prg::from_seed(seed).fork(b"child2").random_block()
+    );
+    assert_ne!(
+        Prg::from_seed(seed).fork(b"child").random_block(),
+        Prg::from_seed_and_nonce(seed, 1)
+            .fork(b"child")
+            .random_block()
+    );
+}
+
+#[test]
+fn prg_par_test() {
+    let seed = rand::random::<Block>();
+    let len = 3 * PAR_CHUNK + 13;
+    let mut prg = Prg::from_seed(seed);
+    let mut prg_par = Prg::from_seed(seed);
+    let mut x = vec![Block::ZERO; len];
+    let mut y = vec![Block::ZERO; len];
+    prg.random_blocks(&mut x);
+    prg_par.random_blocks_par(&mut y);
+    assert_eq!(x, y);
+    assert_eq!(prg.counter(), prg_par.counter());
+
+    // Start from an unaligned position.
+    prg.random_byte();
+    prg_par.random_byte();
+    prg.random_blocks(&mut x[..5]);
+    prg_par.random_blocks_par(&mut y[..5]);
+    prg.random_blocks(&mut x);
+    prg_par.random_blocks_par(&mut y);
+    assert_eq!(x, y);
+    assert_eq!(prg.next_u64(), prg_par.next_u64());
+}
+
+#[cfg(test)]
+fn chi_square(counts: &[usize]) -> f64 {
+    let total: usize = counts.iter().sum();
+    let expected = total as f64 / counts.len() as f64;
+    counts
+        .iter()
+        .map(|&c| (c as f64 - expected).powi(2) / expected)
+        .sum()
+}
+
+#[test]
+fn prg_sampling_test() {
+    let mut prg = Prg::new();
+
+    // The critical value of the chi-square distribution with 5 degrees of freedom at p = 10^-6 is about 35.9.
+    let mut counts = [0usize; 6];
+    for _ in 0..60000 {
+        counts[prg.random_below(6) as usize] += 1;
+    }
+    assert!(chi_square(&counts) < 40.0);
+
+    let mut counts = [0usize; 6];
+    let perms = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    for _ in 0..60000 {
+        let perm = prg.random_permutation(3);
+        counts[perms.iter().position(|p| p[..] == perm[..]).unwrap()] += 1;
+    }
+    assert!(chi_square(&counts) < 40.0);
+
+    // The number of ones deviates from the mean by at most 6 standard deviations.
+    let bits = prg.random_packed_bits(1 << 16);
+    let ones: u32 = bits.iter().map(|b| b.count_ones()).sum();
+    assert!(ones.abs_diff(1 << 15) < 6 * 128);
+    let bits = prg.random_packed_bits(13);
+    assert_eq!(bits.len(), 2);
+    assert_eq!(bits[1] >> 5, 0);
+
+    // The critical value of the chi-square distribution with 9 degrees of freedom at p = 10^-6 is about 46.
+    let mut counts = [0usize; 10];
+    for _ in 0..20000 {
+        let positions = prg.random_exact_weight_positions(10, 3);
+        assert_eq!(positions.len(), 3);
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        for i in positions {
+            counts[i] += 1;
+        }
+    }
+    assert!(chi_square(&counts) < 50.0);
+    assert_eq!(prg.random_exact_weight_positions(5, 5), [0, 1, 2, 3, 4]);
+
+    let mut counts = [0usize; 10];
+    for _ in 0..20000 {
+        let positions = prg.random_regular_positions(10, 3);
+        assert!(positions[0] < 3 && (3..6).contains(&positions[1]) && positions[2] >= 6);
+        counts[positions[2] - 6] += 1;
+    }
+    assert!(chi_square(&counts[..4]) < 40.0);
+
+    let mut e = vec![Block::ONES; 100];
+    let positions = prg.random_regular_noise(&mut e, 10);
+    for (i, x) in e.iter().enumerate() {
+        assert_eq!(*x != Block::ZERO, positions.contains(&i));
+    }
+    assert_ne!(prg.random_nonzero_block(), Block::ZERO);
+}
+
+#[test]
+fn prg_state_test() {
+    let mut prg = Prg::from_seed_and_nonce(rand::random::<Block>(), 5);
+    let state = prg.export_state();
+    assert_eq!(state.index, RESULTS_LEN);
+    assert_eq!(
+        Prg::import_state(&state).random_block(),
+        prg.clone().random_block()
+    );
+
+    for len in [1, 3, 4, 31, 32, 33, 100] {
+        let mut bytes = vec![0u8; len];
+        prg.random_bytes(&mut bytes);
+        let state = prg.export_state();
+        let mut resumed = Prg::import_state(&state);
+        assert_eq!(resumed.export_state(), state);
+        assert_eq!(resumed.counter(), prg.counter());
+
+        let mut x = vec![0u8; 77];
+        let mut y = vec![0u8; 77];
+        prg.clone().random_bytes(&mut x);
+        resumed.random_bytes(&mut y);
+        assert_eq!(x, y);
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        let state = prg.export_state();
+        let json = serde_json::to_string(&state).unwrap();
+        let mut resumed = Prg::import_state(&serde_json::from_str(&json).unwrap());
+        assert_eq!(resumed.random_block(), prg.random_block());
+    }
+}
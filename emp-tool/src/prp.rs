@@ -1,15 +1,20 @@
 //! An implementation of Pseudo Random Permutation (PRP) based on AES.
 
-use crate::{aes::Aes, Block};
+use crate::{
+    aes::{Aes, AesDec},
+    Block,
+};
+use std::sync::OnceLock;
 
-/// Struct of PRP
-pub struct Prp(Aes);
+/// Struct of PRP\
+/// The decryption key schedule is built on the first inverse permutation.
+pub struct Prp(Aes, OnceLock<AesDec>);
 
 impl Prp {
     /// New an instance of Prp.
     #[inline(always)]
     pub fn new(seed: Block) -> Self {
-        Prp(Aes::new(seed))
+        Prp(Aes::new(seed), OnceLock::new())
     }
 
     /// Permute one block.
@@ -29,4 +34,45 @@ impl Prp {
     pub fn permute_block_slice(&self, blks: &mut [Block]) {
         self.0.encrypt_block_slice(blks);
     }
+
+    // Get the decryption key schedule.
+    #[inline(always)]
+    fn dec(&self) -> &AesDec {
+        self.1.get_or_init(|| self.0.decryptor())
+    }
+
+    /// Inverse permute one block.
+    #[inline(always)]
+    pub fn inverse_permute_block(&self, blk: Block) -> Block {
+        self.dec().decrypt_block(blk)
+    }
+
+    /// Inverse permute many blocks.
+    #[inline(always)]
+    pub fn inverse_permute_many_blocks<const N: usize>(&self, blks: [Block; N]) -> [Block; N] {
+        self.dec().decrypt_many_blocks(blks)
+    }
+
+    /// Inverse permute block slice.
+    #[inline(always)]
+    pub fn inverse_permute_block_slice(&self, blks: &mut [Block]) {
+        self.dec().decrypt_block_slice(blks);
+    }
+}
+
+#[test]
+fn prp_test() {
+    let prp = Prp::new(rand::random::<Block>());
+    let x = rand::random::<Block>();
+    assert_eq!(prp.inverse_permute_block(prp.permute_block(x)), x);
+
+    let blks = rand::random::<[Block; 5]>();
+    let y = prp.permute_many_blocks(blks);
+    assert_eq!(prp.inverse_permute_many_blocks(y), blks);
+
+    let mut z = blks.to_vec();
+    prp.permute_block_slice(&mut z);
+    assert_eq!(z, y.to_vec());
+    prp.inverse_permute_block_slice(&mut z);
+    assert_eq!(z, blks.to_vec());
 }
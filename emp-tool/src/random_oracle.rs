@@ -0,0 +1,179 @@
+//! Implement a domain-separated random oracle with extendable output based on SHAKE256.
+
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256, Shake256Reader,
+};
+
+use crate::Block;
+
+/// The prefix absorbed before the label of every random oracle.
+const DOMAIN_PREFIX: &[u8] = b"emp-rust/random-oracle/v1";
+
+/// A random oracle instantiated with SHAKE256.\
+/// Each instance is bound to a domain-separation `label`, such that oracles with different labels are independent.\
+/// Absorb the input with `update*`, then call `finalize` to squeeze arbitrarily long output.
+#[derive(Clone)]
+pub struct RandomOracle(Shake256);
+
+impl RandomOracle {
+    /// New a random oracle with the domain-separation label.
+    #[inline(always)]
+    pub fn new(label: &[u8]) -> Self {
+        let mut hasher = Shake256::default();
+        hasher.update(&(DOMAIN_PREFIX.len() as u64).to_le_bytes());
+        hasher.update(DOMAIN_PREFIX);
+        hasher.update(&(label.len() as u64).to_le_bytes());
+        hasher.update(label);
+        Self(hasher)
+    }
+
+    /// Update bytes.
+    #[inline(always)]
+    pub fn update(&mut self, m: &[u8]) {
+        self.0.update(m);
+    }
+
+    /// Update block.
+    #[inline(always)]
+    pub fn update_block(&mut self, m: Block) {
+        self.update(m.as_ref());
+    }
+
+    /// Update block slice.
+    #[inline(always)]
+    pub fn update_block_slice(&mut self, m: &[Block]) {
+        self.update(bytemuck::cast_slice(m));
+    }
+
+    /// Finalize the input and return the reader of the output stream.
+    #[inline(always)]
+    pub fn finalize(self) -> RandomOracleReader {
+        RandomOracleReader(self.0.finalize_xof())
+    }
+
+    /// Hash bytes once to a block.
+    #[inline(always)]
+    pub fn hash_to_block(label: &[u8], m: &[u8]) -> Block {
+        let mut ro = Self::new(label);
+        ro.update(m);
+        ro.finalize().read_block()
+    }
+
+    /// Hash bytes once to blocks.
+    #[inline(always)]
+    pub fn hash_to_blocks(label: &[u8], m: &[u8], out: &mut [Block]) {
+        let mut ro = Self::new(label);
+        ro.update(m);
+        ro.finalize().read_blocks(out);
+    }
+
+    /// Hash bytes once to an element of `Z_p`, where `p = modulus`.
+    #[inline(always)]
+    pub fn hash_to_field(label: &[u8], m: &[u8], modulus: u64) -> u64 {
+        let mut ro = Self::new(label);
+        ro.update(m);
+        ro.finalize().read_field(modulus)
+    }
+
+    /// Hash bytes once to bits.
+    #[inline(always)]
+    pub fn hash_to_bits(label: &[u8], m: &[u8], out: &mut [bool]) {
+        let mut ro = Self::new(label);
+        ro.update(m);
+        ro.finalize().read_bits(out);
+    }
+}
+
+/// The output stream of a finalized `RandomOracle`.
+pub struct RandomOracleReader(Shake256Reader);
+
+impl RandomOracleReader {
+    /// Read bytes.
+    #[inline(always)]
+    pub fn read_bytes(&mut self, buf: &mut [u8]) {
+        self.0.read(buf);
+    }
+
+    /// Read a block, which is also a uniform element of GF(2^128) as used by `Block::gfmul`.
+    #[inline(always)]
+    pub fn read_block(&mut self) -> Block {
+        let mut res = Block::ZERO;
+        self.read_bytes(res.as_mut());
+        res
+    }
+
+    /// Read blocks.
+    #[inline(always)]
+    pub fn read_blocks(&mut self, buf: &mut [Block]) {
+        self.read_bytes(bytemuck::cast_slice_mut(buf));
+    }
+
+    /// Read an element of `Z_p`, where `p = modulus`.\
+    /// A 128-bit output is reduced modulo `p`, so the statistical distance to uniform is at most `2^-64`.
+    #[inline(always)]
+    pub fn read_field(&mut self, modulus: u64) -> u64 {
+        assert!(modulus > 0);
+        let mut buf = [0u8; 16];
+        self.read_bytes(&mut buf);
+        (u128::from_le_bytes(buf) % modulus as u128) as u64
+    }
+
+    /// Read bits, where each byte of output fills 8 bits starting from the least significant one.
+    #[inline(always)]
+    pub fn read_bits(&mut self, buf: &mut [bool]) {
+        let mut bytes = [0u8; 64];
+        for chunk in buf.chunks_mut(512) {
+            let bytes = &mut bytes[..chunk.len().div_ceil(8)];
+            self.read_bytes(bytes);
+            for (i, bit) in chunk.iter_mut().enumerate() {
+                *bit = (bytes[i / 8] >> (i % 8)) & 1 == 1;
+            }
+        }
+    }
+}
+
+#[test]
+fn random_oracle_test() {
+    let m = b"message";
+    let x = RandomOracle::hash_to_block(b"label", m);
+    assert_eq!(x, RandomOracle::hash_to_block(b"label", m));
+    assert_ne!(x, RandomOracle::hash_to_block(b"label2", m));
+    assert_ne!(
+        RandomOracle::hash_to_block(b"ab", b"c"),
+        RandomOracle::hash_to_block(b"a", b"bc")
+    );
+
+    // The output is a single stream, independent of how it is read.
+    let mut blks = [Block::ZERO; 5];
+    RandomOracle::hash_to_blocks(b"label", m, &mut blks);
+    assert_eq!(blks[0], x);
+    let mut ro = RandomOracle::new(b"label");
+    ro.update(&m[..3]);
+    ro.update(&m[3..]);
+    let mut reader = ro.finalize();
+    let mut bytes = [0u8; 16 * 5];
+    reader.read_bytes(&mut bytes[..7]);
+    reader.read_bytes(&mut bytes[7..]);
+    assert_eq!(bytes[..], *bytemuck::cast_slice::<Block, u8>(&blks));
+
+    let mut bits = vec![false; 1000];
+    RandomOracle::hash_to_bits(b"label", m, &mut bits);
+    let bytes = crate::utils::pack_bits_to_bytes(&bits);
+    let mut expected = [0u8; 125];
+    let mut ro = RandomOracle::new(b"label");
+    ro.update(m);
+    ro.finalize().read_bytes(&mut expected);
+    assert_eq!(bytes, expected);
+
+    let p = (1u64 << 61) - 1;
+    let mut reader = RandomOracle::new(b"field").finalize();
+    for _ in 0..100 {
+        assert!(reader.read_field(p) < p);
+    }
+    assert_eq!(RandomOracle::hash_to_field(b"field", m, 7), {
+        let mut ro = RandomOracle::new(b"field");
+        ro.update(m);
+        (u128::from(ro.finalize().read_block()) % 7) as u64
+    });
+}
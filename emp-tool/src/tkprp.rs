@@ -1,7 +1,11 @@
 //! Implement the two-key PRG as G(k) = PRF_seed0(k)\xor k || PRF_seed1(k)\xor k
 //! Refer to (<https://www.usenix.org/system/files/conference/nsdi17/nsdi17-wang-frank.pdf>, Page 8)
+//! and its generalization with `q` keys as G(k) = PRF_seed0(k)\xor k || ... || PRF_seed{q-1}(k)\xor k for q-ary trees.
 
-use crate::{aes::Aes, Block};
+use crate::{
+    aes::{Aes, AES_BATCH},
+    Block,
+};
 
 /// Struct of two-key prp.
 pub struct TwoKeyPrp([Aes; 2]);
@@ -23,6 +27,12 @@ impl TwoKeyPrp {
         children[1] ^= parent;
     }
 
+    /// expand 1 to the `i`-th child
+    #[inline(always)]
+    pub(crate) fn expand_child(&self, parent: Block, i: usize) -> Block {
+        self.0[i].encrypt_block(parent) ^ parent
+    }
+
     /// expand 2 to 4
     //     p[0]            p[1]
     // c[0]    c[1]    c[2]    c[3]
@@ -84,4 +94,115 @@ impl TwoKeyPrp {
         children[1] ^= tmp[4];
         children[0] ^= tmp[0];
     }
+
+    /// expand `N` parents to `2 * N` children, where `children[2 * i]` and `children[2 * i + 1]` are the children of `parents[i]`
+    #[inline(always)]
+    pub(crate) fn expand_many<const N: usize>(&self, children: &mut [Block], parents: &[Block; N]) {
+        let mut tmp = [*parents; 2];
+        Aes::para_encrypt::<2, N>(self.0, tmp.as_flattened_mut());
+        for (((c, p), t0), t1) in children[..2 * N]
+            .chunks_exact_mut(2)
+            .zip(parents.iter())
+            .zip(tmp[0].iter())
+            .zip(tmp[1].iter())
+        {
+            c[0] = *t0 ^ *p;
+            c[1] = *t1 ^ *p;
+        }
+    }
+}
+
+/// Struct of multi-key prp, which expands each parent into `q` children with `q` keys.\
+/// With two keys, the output is the same as `TwoKeyPrp` with the same keys.
+pub struct MultiKeyPrp(Vec<Aes>);
+
+impl MultiKeyPrp {
+    /// New an instance of MultiKeyPrp with `q = seeds.len()` keys.
+    #[inline(always)]
+    pub fn new(seeds: &[Block]) -> Self {
+        assert!(seeds.len() >= 2);
+        Self(seeds.iter().map(|&seed| Aes::new(seed)).collect())
+    }
+
+    /// New an instance of MultiKeyPrp with the fixed keys `[0, 1, ..., q - 1]`.\
+    /// The binary case uses the same keys as `GgmTree`.
+    #[inline(always)]
+    pub fn with_arity(q: usize) -> Self {
+        let seeds: Vec<Block> = (0..q as u128).map(Block::from).collect();
+        Self::new(&seeds)
+    }
+
+    /// Get the arity `q`.
+    #[inline(always)]
+    pub fn arity(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Expand `n` parents into `q * n` children, where `children[q * i + j] = PRF_seedj(parents[i]) xor parents[i]`.
+    pub fn expand(&self, children: &mut [Block], parents: &[Block]) {
+        let q = self.arity();
+        assert_eq!(children.len(), q * parents.len());
+
+        // Blocks of each key are consecutive in `tmp`.
+        let mut tmp = vec![Block::ZERO; q * AES_BATCH.min(parents.len())];
+        for (children, parents) in children
+            .chunks_mut(q * AES_BATCH)
+            .zip(parents.chunks(AES_BATCH))
+        {
+            let m = parents.len();
+            let tmp = &mut tmp[..q * m];
+            for batch in tmp.chunks_exact_mut(m) {
+                batch.copy_from_slice(parents);
+            }
+            Aes::para_encrypt_slice(&self.0, tmp);
+            for (i, (children, p)) in children.chunks_exact_mut(q).zip(parents).enumerate() {
+                for (j, c) in children.iter_mut().enumerate() {
+                    *c = tmp[j * m + i] ^ *p;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn multi_key_prp_test() {
+    let seeds = [rand::random::<Block>(), rand::random::<Block>()];
+    let tkprp = TwoKeyPrp::new(seeds);
+    let mkprp = MultiKeyPrp::new(&seeds);
+    let parents = rand::random::<[Block; 4]>();
+    let mut expected = [Block::ZERO; 8];
+    let mut children = [Block::ZERO; 8];
+
+    tkprp.expand_1to2(&mut expected, parents[0]);
+    mkprp.expand(&mut children[..2], &parents[..1]);
+    assert_eq!(children[..2], expected[..2]);
+    tkprp.expand_2to4(&mut expected, &parents);
+    mkprp.expand(&mut children[..4], &parents[..2]);
+    assert_eq!(children[..4], expected[..4]);
+    tkprp.expand_4to8(&mut expected, &parents);
+    mkprp.expand(&mut children, &parents);
+    assert_eq!(children, expected);
+    tkprp.expand_many(&mut children, &parents);
+    assert_eq!(children, expected);
+
+    let parents = rand::random::<[Block; 16]>();
+    let mut children = [Block::ZERO; 32];
+    tkprp.expand_many(&mut children, &parents);
+    for (c, p) in children.chunks_exact(8).zip(parents.chunks_exact(4)) {
+        tkprp.expand_4to8(&mut expected, p);
+        assert_eq!(c, expected);
+    }
+
+    for q in [3, 5] {
+        let mkprp = MultiKeyPrp::with_arity(q);
+        let keys: Vec<Aes> = (0..q as u128).map(|j| Aes::new(Block::from(j))).collect();
+        let parents: Vec<Block> = (0..37).map(|_| rand::random::<Block>()).collect();
+        let mut children = vec![Block::ZERO; q * parents.len()];
+        mkprp.expand(&mut children, &parents);
+        for (i, p) in parents.iter().enumerate() {
+            for (j, key) in keys.iter().enumerate() {
+                assert_eq!(children[q * i + j], key.encrypt_block(*p) ^ *p);
+            }
+        }
+    }
 }
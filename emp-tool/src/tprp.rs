@@ -0,0 +1,168 @@
+//! An implementation of Tweakable Pseudo Random Permutation (TPRP) based on AES.
+
+use crate::{
+    aes::{Aes, AesDec, AES_BATCH},
+    Block,
+};
+use std::sync::OnceLock;
+
+/// Struct of tweakable PRP.\
+/// It is the XEX construction (cf. <https://www.cs.ucdavis.edu/~rogaway/papers/offsets.pdf>) with `π_t(x) = AES(x ⊕ Δ) ⊕ Δ`, where `Δ = 2 · AES(t)` in `GF(2^128)`.
+/// The doubling of `AES(t)` is necessary for `TPrp` to be a strong tweakable PRP, i.e., secure with inverse queries.
+/// The decryption key schedule is built on the first inverse permutation.
+pub struct TPrp(Aes, OnceLock<AesDec>);
+
+impl TPrp {
+    /// New an instance of TPrp.
+    #[inline(always)]
+    pub fn new(seed: Block) -> Self {
+        TPrp(Aes::new(seed), OnceLock::new())
+    }
+
+    // Compute `2 · x` in `GF(2^128)` with polynomial `x^128 + x^7 + x^2 + x + 1`.
+    #[inline(always)]
+    fn double(x: Block) -> Block {
+        let x = u128::from(x);
+        Block::from((x << 1) ^ ((x >> 127) * 0x87))
+    }
+
+    // Compute the masks of many tweaks.
+    #[inline(always)]
+    fn masks<const N: usize>(&self, tweaks: [Block; N]) -> [Block; N] {
+        self.0.encrypt_many_blocks(tweaks).map(Self::double)
+    }
+
+    // Get the decryption key schedule.
+    #[inline(always)]
+    fn dec(&self) -> &AesDec {
+        self.1.get_or_init(|| self.0.decryptor())
+    }
+
+    // Compute the masks of a tweak slice into `masks`.
+    #[inline(always)]
+    fn mask_slice(&self, masks: &mut [Block], tweaks: &[Block]) {
+        masks.copy_from_slice(tweaks);
+        self.0.encrypt_block_slice(masks);
+        for m in masks.iter_mut() {
+            *m = Self::double(*m);
+        }
+    }
+
+    /// Permute one block with a tweak.
+    #[inline(always)]
+    pub fn permute(&self, tweak: Block, blk: Block) -> Block {
+        self.permute_many_blocks([tweak], [blk])[0]
+    }
+
+    /// Permute many blocks, where `blks[i]` is permuted with `tweaks[i]`.
+    #[inline(always)]
+    pub fn permute_many_blocks<const N: usize>(
+        &self,
+        tweaks: [Block; N],
+        blks: [Block; N],
+    ) -> [Block; N] {
+        let masks = self.masks(tweaks);
+        let x: [Block; N] = std::array::from_fn(|i| blks[i] ^ masks[i]);
+        let mut y = self.0.encrypt_many_blocks(x);
+        for (y, m) in y.iter_mut().zip(masks.iter()) {
+            *y ^= *m;
+        }
+        y
+    }
+
+    /// Permute block slice, where `blks[i]` is permuted with `tweaks[i]`.
+    #[inline(always)]
+    pub fn permute_block_slice(&self, tweaks: &[Block], blks: &mut [Block]) {
+        assert_eq!(tweaks.len(), blks.len());
+        let mut buf = [Block::ZERO; AES_BATCH];
+        for (chunk, tweaks) in blks.chunks_mut(AES_BATCH).zip(tweaks.chunks(AES_BATCH)) {
+            let masks = &mut buf[..chunk.len()];
+            self.mask_slice(masks, tweaks);
+            for (x, m) in chunk.iter_mut().zip(masks.iter()) {
+                *x ^= *m;
+            }
+            self.0.encrypt_block_slice(chunk);
+            for (x, m) in chunk.iter_mut().zip(masks.iter()) {
+                *x ^= *m;
+            }
+        }
+    }
+
+    /// Inverse permute one block with a tweak.
+    #[inline(always)]
+    pub fn inverse_permute(&self, tweak: Block, blk: Block) -> Block {
+        self.inverse_permute_many_blocks([tweak], [blk])[0]
+    }
+
+    /// Inverse permute many blocks, where `blks[i]` is inverse permuted with `tweaks[i]`.
+    #[inline(always)]
+    pub fn inverse_permute_many_blocks<const N: usize>(
+        &self,
+        tweaks: [Block; N],
+        blks: [Block; N],
+    ) -> [Block; N] {
+        let masks = self.masks(tweaks);
+        let y: [Block; N] = std::array::from_fn(|i| blks[i] ^ masks[i]);
+        let mut x = self.dec().decrypt_many_blocks(y);
+        for (x, m) in x.iter_mut().zip(masks.iter()) {
+            *x ^= *m;
+        }
+        x
+    }
+
+    /// Inverse permute block slice, where `blks[i]` is inverse permuted with `tweaks[i]`.
+    #[inline(always)]
+    pub fn inverse_permute_block_slice(&self, tweaks: &[Block], blks: &mut [Block]) {
+        assert_eq!(tweaks.len(), blks.len());
+        let mut buf = [Block::ZERO; AES_BATCH];
+        for (chunk, tweaks) in blks.chunks_mut(AES_BATCH).zip(tweaks.chunks(AES_BATCH)) {
+            let masks = &mut buf[..chunk.len()];
+            self.mask_slice(masks, tweaks);
+            for (x, m) in chunk.iter_mut().zip(masks.iter()) {
+                *x ^= *m;
+            }
+            self.dec().decrypt_block_slice(chunk);
+            for (x, m) in chunk.iter_mut().zip(masks.iter()) {
+                *x ^= *m;
+            }
+        }
+    }
+}
+
+#[test]
+fn tprp_test() {
+    let x = rand::random::<Block>();
+    assert_eq!(TPrp::double(x), x.gfmul(&Block::from(2u128)));
+
+    let tprp = TPrp::new(rand::random::<Block>());
+    let t = rand::random::<Block>();
+    let y = tprp.permute(t, x);
+    let delta = TPrp::double(tprp.0.encrypt_block(t));
+    assert_eq!(y, tprp.0.encrypt_block(x ^ delta) ^ delta);
+    assert_eq!(tprp.inverse_permute(t, y), x);
+    assert_ne!(tprp.permute(t ^ Block::from(1u128), x), y);
+
+    let tweaks: Vec<Block> = (0..100).map(|i| Block::from([i, 0])).collect();
+    let blks: Vec<Block> = (0..100).map(|_| rand::random::<Block>()).collect();
+    let expected: Vec<Block> = tweaks
+        .iter()
+        .zip(blks.iter())
+        .map(|(t, x)| tprp.permute(*t, *x))
+        .collect();
+
+    let y = tprp.permute_many_blocks::<5>(
+        tweaks[..5].try_into().unwrap(),
+        blks[..5].try_into().unwrap(),
+    );
+    assert_eq!(y[..], expected[..5]);
+    assert_eq!(
+        tprp.inverse_permute_many_blocks(tweaks[..5].try_into().unwrap(), y)[..],
+        blks[..5]
+    );
+
+    let mut z = blks.clone();
+    tprp.permute_block_slice(&tweaks, &mut z);
+    assert_eq!(z, expected);
+    tprp.inverse_permute_block_slice(&tweaks, &mut z);
+    assert_eq!(z, blks);
+}
@@ -0,0 +1,216 @@
+//! Implement a Fiat-Shamir transcript in the style of Merlin, based on `hash::Hash`.
+
+use crate::{hash::Hash, io_channel::IOChannel, prg::Prg, utils::pack_bits_to_bytes, Block};
+use rand_core::SeedableRng;
+use std::io::Result;
+
+/// The prefix absorbed at the beginning of every transcript.
+const DOMAIN_PREFIX: &[u8] = b"emp-rust/transcript/v1";
+
+/// The label of messages absorbed by `TranscriptIO`.
+const IO_LABEL: &[u8] = b"io";
+
+/// A Fiat-Shamir transcript.\
+/// Every message is absorbed together with a label and its length, so distinct sequences of labelled messages give independent challenges.\
+/// Squeezing a challenge also absorbs its label and length, so consecutive challenges are independent.
+#[derive(Clone)]
+pub struct Transcript(Hash);
+
+impl Transcript {
+    /// New a transcript with the domain-separation label.
+    #[inline(always)]
+    pub fn new(label: &[u8]) -> Self {
+        let mut transcript = Self(Hash::new());
+        transcript.append_message(DOMAIN_PREFIX, label);
+        transcript
+    }
+
+    /// Append labelled bytes.
+    #[inline(always)]
+    pub fn append_message(&mut self, label: &[u8], m: &[u8]) {
+        self.0.update(&(label.len() as u64).to_le_bytes());
+        self.0.update(label);
+        self.0.update(&(m.len() as u64).to_le_bytes());
+        self.0.update(m);
+    }
+
+    /// Append a labelled `u64`.
+    #[inline(always)]
+    pub fn append_u64(&mut self, label: &[u8], m: u64) {
+        self.append_message(label, &m.to_le_bytes());
+    }
+
+    /// Append a labelled block.
+    #[inline(always)]
+    pub fn append_block(&mut self, label: &[u8], m: Block) {
+        self.append_message(label, m.as_ref());
+    }
+
+    /// Append a labelled block slice.
+    #[inline(always)]
+    pub fn append_block_slice(&mut self, label: &[u8], m: &[Block]) {
+        self.append_message(label, bytemuck::cast_slice(m));
+    }
+
+    /// Append a labelled bool slice.\
+    /// The bools are packed into bytes following the number of bools.
+    #[inline(always)]
+    pub fn append_bools(&mut self, label: &[u8], m: &[bool]) {
+        let mut bytes = (m.len() as u64).to_le_bytes().to_vec();
+        bytes.extend(pack_bits_to_bytes(m));
+        self.append_message(label, &bytes);
+    }
+
+    /// Fill `buf` with labelled challenge bytes.
+    pub fn challenge_bytes(&mut self, label: &[u8], buf: &mut [u8]) {
+        self.append_u64(label, buf.len() as u64);
+        let digest = self.0.finalize();
+        // Ratchet the state, such that the next challenge is independent of this one.
+        self.0.update(&digest);
+
+        for (i, chunk) in buf.chunks_mut(Hash::DIGEST_SIZE).enumerate() {
+            let mut hasher = Hash::new();
+            hasher.update(&digest);
+            hasher.update(&(i as u64).to_le_bytes());
+            chunk.copy_from_slice(&hasher.finalize()[..chunk.len()]);
+        }
+    }
+
+    /// Squeeze a labelled challenge block.
+    #[inline(always)]
+    pub fn challenge_block(&mut self, label: &[u8]) -> Block {
+        let mut res = Block::ZERO;
+        self.challenge_bytes(label, res.as_mut());
+        res
+    }
+
+    /// Fill `buf` with labelled challenge blocks.
+    #[inline(always)]
+    pub fn challenge_blocks(&mut self, label: &[u8], buf: &mut [Block]) {
+        self.challenge_bytes(label, bytemuck::cast_slice_mut(buf));
+    }
+
+    /// Squeeze a labelled challenge and use it as the seed of a `Prg`.\
+    /// This is preferred when a large number of challenges is needed.
+    #[inline(always)]
+    pub fn challenge_prg(&mut self, label: &[u8]) -> Prg {
+        Prg::from_seed(self.challenge_block(label))
+    }
+}
+
+/// An IO channel that absorbs every sent and received message into a transcript.\
+/// Each call of `send_bytes` or `recv_bytes` is absorbed as one message, so the receiver must receive a message with the same length as it was sent.
+/// This holds for the paired `send_*` and `recv_*` methods of `IOChannel`.
+pub struct TranscriptIO<'a, IO: IOChannel> {
+    io: &'a mut IO,
+    transcript: &'a mut Transcript,
+}
+
+impl<'a, IO: IOChannel> TranscriptIO<'a, IO> {
+    /// New a `TranscriptIO` that wraps `io` and absorbs the messages into `transcript`.
+    #[inline(always)]
+    pub fn new(io: &'a mut IO, transcript: &'a mut Transcript) -> Self {
+        Self { io, transcript }
+    }
+
+    /// Get the transcript, e.g., to append messages that are not sent or to squeeze challenges.
+    #[inline(always)]
+    pub fn transcript(&mut self) -> &mut Transcript {
+        self.transcript
+    }
+
+    /// Get the wrapped IO channel, whose messages are not absorbed.
+    #[inline(always)]
+    pub fn io(&mut self) -> &mut IO {
+        self.io
+    }
+}
+
+impl<IO: IOChannel> IOChannel for TranscriptIO<'_, IO> {
+    #[inline(always)]
+    fn send_bytes(&mut self, buffer: &[u8]) -> Result<()> {
+        self.transcript.append_message(IO_LABEL, buffer);
+        self.io.send_bytes(buffer)
+    }
+
+    #[inline(always)]
+    fn recv_bytes(&mut self, buffer: &mut [u8]) -> Result<()> {
+        self.io.recv_bytes(buffer)?;
+        self.transcript.append_message(IO_LABEL, buffer);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        self.io.flush()
+    }
+}
+
+#[test]
+fn transcript_test() {
+    let mut t0 = Transcript::new(b"test");
+    let mut t1 = Transcript::new(b"test");
+    t0.append_message(b"ab", b"c");
+    t1.append_message(b"a", b"bc");
+    assert_ne!(t0.challenge_block(b"x"), t1.challenge_block(b"x"));
+
+    let mut t0 = Transcript::new(b"test");
+    let mut t1 = Transcript::new(b"test");
+    t0.append_bools(b"bits", &[true, false, false]);
+    t1.append_bools(b"bits", &[true]);
+    assert_ne!(t0.challenge_block(b"x"), t1.challenge_block(b"x"));
+
+    // Challenges are deterministic, and consecutive challenges differ.
+    let mut t0 = Transcript::new(b"test");
+    t0.append_block(b"blk", Block::ONES);
+    let mut t1 = t0.clone();
+    let x = t0.challenge_block(b"x");
+    assert_eq!(x, t1.challenge_block(b"x"));
+    assert_ne!(x, t0.challenge_block(b"x"));
+    let mut blks = [Block::ZERO; 5];
+    t0.challenge_blocks(b"y", &mut blks);
+    assert!(blks.iter().all(|&b| b != Block::ZERO));
+}
+
+#[test]
+fn transcript_io_test() {
+    use crate::io_channel::NetIO;
+    use rand::Rng;
+
+    let addr = "127.0.0.1:12346";
+    const NUM: usize = 10;
+    let send_block_vec = rand::random::<[Block; NUM]>();
+    let send_bool_vec = rand::random::<[bool; NUM]>();
+
+    let handle = std::thread::spawn(move || {
+        let mut io = NetIO::new(true, addr).unwrap();
+        let mut transcript = Transcript::new(b"test");
+        let mut tio = TranscriptIO::new(&mut io, &mut transcript);
+        tio.send_block_vec(&send_block_vec).unwrap();
+        let x = tio.recv_block().unwrap();
+        tio.send_bool_vec(&send_bool_vec).unwrap();
+        tio.flush().unwrap();
+        let chal = tio.transcript().challenge_block(b"chal");
+        (x, chal, transcript.challenge_prg(b"prg").random_block())
+    });
+
+    let mut io = NetIO::new(false, addr).unwrap();
+    let mut transcript = Transcript::new(b"test");
+    let mut tio = TranscriptIO::new(&mut io, &mut transcript);
+    let blks = tio.recv_block_vec(NUM).unwrap();
+    let x = rand::thread_rng().gen::<Block>();
+    tio.send_block(&x).unwrap();
+    let bools = tio.recv_bool_vec(NUM).unwrap();
+    let chal = tio.transcript().challenge_block(b"chal");
+    let r = transcript.challenge_prg(b"prg").random_block();
+
+    assert_eq!(blks, send_block_vec);
+    assert_eq!(bools, send_bool_vec);
+    assert_eq!(handle.join().unwrap(), (x, chal, r));
+
+    let mut expected = Transcript::new(b"test");
+    expected.append_block_slice(IO_LABEL, &send_block_vec);
+    expected.append_block(IO_LABEL, x);
+    expected.append_message(IO_LABEL, &pack_bits_to_bytes(&send_bool_vec));
+    assert_eq!(expected.challenge_block(b"chal"), chal);
+}
@@ -0,0 +1,174 @@
+//! Implement a GHASH-style universal hash over GF(2^128), i.e., polynomial evaluation at a key.
+
+use crate::Block;
+
+/// The number of blocks multiplied before one reduction.
+const BATCH: usize = 16;
+
+/// A polynomial universal hash over GF(2^128) with the reduction polynomial of `Block::gfmul`.\
+/// For a key `k` and blocks `m_1, ..., m_n`, the output is `m_1 * k^n + m_2 * k^(n-1) + ... + m_n * k`.
+/// Two distinct messages with the same length collide with probability at most `n / 2^128` over a random key.\
+/// The hash is computed with Horner's rule over batches of `BATCH` blocks, where the products in a batch are summed without reduction and reduced once.
+/// Blocks can be absorbed in a streaming way, and the output only depends on the concatenation of the absorbed blocks.
+///
+/// To use it as a one-time MAC, mask the output with a fresh random block, or use `finalize_with_length` if the messages may have different lengths.
+#[derive(Clone, Debug)]
+pub struct UniversalHash {
+    /// The powers `[k^BATCH, k^(BATCH-1), ..., k]` of the key.
+    powers: [Block; BATCH],
+
+    /// The hash of the absorbed batches.
+    acc: Block,
+
+    /// The blocks that do not fill a batch yet.
+    buf: [Block; BATCH],
+
+    /// The number of blocks in `buf`.
+    buf_len: usize,
+
+    /// The number of absorbed blocks.
+    len: u64,
+}
+
+impl UniversalHash {
+    /// New a universal hash with the key.
+    #[inline(always)]
+    pub fn new(key: Block) -> Self {
+        let mut powers = [key; BATCH];
+        for i in (0..BATCH - 1).rev() {
+            powers[i] = powers[i + 1].gfmul(&key);
+        }
+        Self {
+            powers,
+            acc: Block::ZERO,
+            buf: [Block::ZERO; BATCH],
+            buf_len: 0,
+            len: 0,
+        }
+    }
+
+    /// Absorb a full batch.
+    #[inline(always)]
+    fn update_batch(&mut self, m: &[Block]) {
+        let mut batch: [Block; BATCH] = m.try_into().unwrap();
+        batch[0] ^= self.acc;
+        let (lo, hi) = Block::inn_prdt_no_red(&batch, &self.powers);
+        self.acc = Block::reduce(&lo, &hi);
+    }
+
+    /// Update block.
+    #[inline(always)]
+    pub fn update_block(&mut self, m: Block) {
+        self.buf[self.buf_len] = m;
+        self.buf_len += 1;
+        self.len += 1;
+        if self.buf_len == BATCH {
+            let buf = self.buf;
+            self.update_batch(&buf);
+            self.buf_len = 0;
+        }
+    }
+
+    /// Update block slice.
+    pub fn update_block_slice(&mut self, mut m: &[Block]) {
+        self.len += m.len() as u64;
+        if self.buf_len != 0 {
+            let len = (BATCH - self.buf_len).min(m.len());
+            self.buf[self.buf_len..self.buf_len + len].copy_from_slice(&m[..len]);
+            self.buf_len += len;
+            m = &m[len..];
+            if self.buf_len < BATCH {
+                return;
+            }
+            let buf = self.buf;
+            self.update_batch(&buf);
+            self.buf_len = 0;
+        }
+
+        let mut chunks = m.chunks_exact(BATCH);
+        for chunk in chunks.by_ref() {
+            self.update_batch(chunk);
+        }
+        let rem = chunks.remainder();
+        self.buf[..rem.len()].copy_from_slice(rem);
+        self.buf_len = rem.len();
+    }
+
+    /// Get the number of absorbed blocks.
+    #[inline(always)]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Check whether no block is absorbed.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Finalize output, which is the polynomial evaluated at the key.
+    #[inline(always)]
+    pub fn finalize(&self) -> Block {
+        if self.buf_len == 0 {
+            return self.acc;
+        }
+        let mut batch = self.buf;
+        batch[0] ^= self.acc;
+        let (lo, hi) =
+            Block::inn_prdt_no_red(&batch[..self.buf_len], &self.powers[BATCH - self.buf_len..]);
+        Block::reduce(&lo, &hi)
+    }
+
+    /// Finalize output after absorbing the number of blocks, as GHASH does.\
+    /// Two distinct messages of at most `n` blocks collide with probability at most `(n + 1) / 2^128` over a random key.
+    #[inline(always)]
+    pub fn finalize_with_length(&self) -> Block {
+        let mut hasher = self.clone();
+        hasher.update_block(Block::from([self.len, 0]));
+        hasher.finalize()
+    }
+
+    /// Hash blocks once.
+    #[inline(always)]
+    pub fn hash_blocks_once(key: Block, m: &[Block]) -> Block {
+        let mut hasher = Self::new(key);
+        hasher.update_block_slice(m);
+        hasher.finalize()
+    }
+}
+
+#[test]
+fn universal_hash_test() {
+    let key = rand::random::<Block>();
+    let m: Vec<Block> = (0..100).map(|_| rand::random::<Block>()).collect();
+
+    // Horner's rule with reduction in every step.
+    let horner = |m: &[Block]| m.iter().fold(Block::ZERO, |acc, x| (acc ^ *x).gfmul(&key));
+    assert_eq!(UniversalHash::hash_blocks_once(key, &m), horner(&m));
+    assert_eq!(
+        UniversalHash::hash_blocks_once(key, &m[..5]),
+        horner(&m[..5])
+    );
+    assert_eq!(UniversalHash::hash_blocks_once(key, &[]), Block::ZERO);
+    assert_eq!(
+        UniversalHash::hash_blocks_once(key, &m[..1]),
+        m[0].gfmul(&key)
+    );
+
+    // Streaming updates.
+    let mut hasher = UniversalHash::new(key);
+    hasher.update_block_slice(&m[..3]);
+    hasher.update_block(m[3]);
+    hasher.update_block_slice(&m[4..20]);
+    hasher.update_block_slice(&m[20..21]);
+    hasher.update_block_slice(&m[21..70]);
+    for x in m[70..].iter() {
+        hasher.update_block(*x);
+    }
+    assert_eq!(hasher.len(), 100);
+    assert_eq!(hasher.finalize(), horner(&m));
+
+    let mut with_len = m.to_vec();
+    with_len.push(Block::from(100u128));
+    assert_eq!(hasher.finalize_with_length(), horner(&with_len));
+}